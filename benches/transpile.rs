@@ -0,0 +1,46 @@
+use simple_compiler::{compile_str, tokenize, CompileOptions};
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const STATEMENTS: usize = 50_000;
+const ITERATIONS: u32 = 10;
+
+/// Builds a program that exercises every statement kind.
+fn generate(statements: usize) -> String {
+    let mut source = String::from("LET counter = 0\nLET total = 1\n");
+
+    for i in 0..statements / 5 {
+        source += &format!("LABEL l{i}\n");
+        source += &format!("LET v{i} = counter * {i} + total / 3\n");
+        source += &format!("IF v{i} >= 10 THEN\n    PRINT \"value {i}\"\nENDIF\n");
+        source += &format!("WHILE counter < v{i} REPEAT\n    LET counter = counter + 1\nENDWHILE\n");
+        source += &format!("PRINT v{i} - total\n");
+    }
+
+    source
+}
+
+fn bench<T>(name: &str, f: impl Fn() -> T) {
+    black_box(f());
+
+    let mut best = Duration::MAX;
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let iteration = Instant::now();
+        black_box(f());
+        best = best.min(iteration.elapsed());
+    }
+    let mean = start.elapsed() / ITERATIONS;
+
+    println!("{name:<12} mean {mean:>10.2?}  best {best:>10.2?}");
+}
+
+fn main() {
+    let source = generate(STATEMENTS);
+    let options = CompileOptions::default();
+    println!("{} bytes, {} lines", source.len(), source.lines().count());
+
+    bench("tokenize", || tokenize(&source).unwrap().len());
+    bench("compile_str", || compile_str(&source, &options).unwrap().len());
+}
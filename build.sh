@@ -7,7 +7,7 @@ function comp {
     if [ $? -ne 0 ]; then
         echo "${TTOUTPUT}"
     else
-        CCOUTPUT=$(${CC} -o ${BN} ${BN}.c)
+        CCOUTPUT=$(${CC} -o ${BN} ${BN}.c -lm)
         if [ $? -ne 0 ]; then
             echo "${CCOUTPUT}"
         else
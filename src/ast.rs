@@ -0,0 +1,230 @@
+use crate::lex::Span;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Program {
+    pub statements: Vec<Statement>,
+    pub functions: Vec<Function>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Function {
+    pub name: String,
+    pub params: Vec<String>,
+    pub param_types: Vec<Option<NumericType>>,
+    pub return_type: Option<NumericType>,
+    pub body: Vec<Statement>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NumericType {
+    Integer,
+    Float,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Statement {
+    Print {
+        items: Vec<Printable>,
+        newline: bool,
+        format: Option<Format>,
+    },
+    If {
+        condition: Expression,
+        body: Vec<Statement>,
+        else_ifs: Vec<ElseIf>,
+        else_body: Option<Vec<Statement>>,
+    },
+    While {
+        condition: Expression,
+        body: Vec<Statement>,
+    },
+    For {
+        variable: String,
+        start: Expression,
+        end: Expression,
+        step: Option<Expression>,
+        body: Vec<Statement>,
+    },
+    Label(String),
+    Goto(String),
+    Gosub {
+        label: String,
+        line: usize,
+    },
+    Return {
+        line: usize,
+        value: Option<Expression>,
+    },
+    Let {
+        name: String,
+        value: Expression,
+    },
+    Dim {
+        name: String,
+        bounds: Vec<usize>,
+        numeric_type: Option<NumericType>,
+    },
+    SetElement {
+        element: Element,
+        value: Expression,
+    },
+    Input {
+        prompt: Option<String>,
+        names: Vec<String>,
+    },
+    Randomize(Expression),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ElseIf {
+    pub condition: Expression,
+    pub body: Vec<Statement>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Element {
+    pub array: String,
+    pub indices: Vec<Expression>,
+    pub span: Span,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Printable {
+    Text(String),
+    Expr(Expression),
+    /// A `,` between items, which moves on to the next tab zone.
+    Zone,
+}
+
+/// A PRINT USING format such as `"+00.##"`: one position per character,
+/// `0` instead of `#` pads with zeros and a leading `+` always shows the sign.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Format {
+    pub width: usize,
+    pub precision: usize,
+    pub sign: bool,
+    pub zeros: bool,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Expression {
+    Number(String),
+    Text(String),
+    Variable(String),
+    Element(Element),
+    Call {
+        name: String,
+        args: Vec<Expression>,
+    },
+    Intrinsic {
+        function: Intrinsic,
+        args: Vec<Expression>,
+    },
+    Unary {
+        op: UnaryOp,
+        operand: Box<Expression>,
+    },
+    Binary {
+        op: BinaryOp,
+        lhs: Box<Expression>,
+        rhs: Box<Expression>,
+    },
+    Group(Box<Expression>),
+}
+
+impl Expression {
+    /// Strings come from literals, `$` variables and concatenations; every
+    /// other expression is a number.
+    pub fn is_text(&self) -> bool {
+        match self {
+            Expression::Text(_) => true,
+            Expression::Variable(name) => name.ends_with('$'),
+            Expression::Binary { op, lhs, .. } => op.is_arithmetic() && lhs.is_text(),
+            Expression::Group(inner) => inner.is_text(),
+            _ => false,
+        }
+    }
+
+    /// Comparisons and logical operators yield a truth value rather than a
+    /// quantity.
+    pub fn is_condition(&self) -> bool {
+        match self {
+            Expression::Binary { op, .. } => !op.is_arithmetic(),
+            Expression::Unary { op: UnaryOp::Not, .. } => true,
+            Expression::Group(inner) => inner.is_condition(),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Intrinsic {
+    Abs,
+    Sqrt,
+    Int,
+    Sin,
+    Cos,
+    Log,
+    Exp,
+    Rnd,
+}
+
+impl Intrinsic {
+    pub fn from_name(name: &str) -> Option<Intrinsic> {
+        match name {
+            "ABS" => Some(Intrinsic::Abs),
+            "SQRT" => Some(Intrinsic::Sqrt),
+            "INT" => Some(Intrinsic::Int),
+            "SIN" => Some(Intrinsic::Sin),
+            "COS" => Some(Intrinsic::Cos),
+            "LOG" => Some(Intrinsic::Log),
+            "EXP" => Some(Intrinsic::Exp),
+            "RND" => Some(Intrinsic::Rnd),
+            _ => None,
+        }
+    }
+
+    pub fn arity(self) -> usize {
+        match self {
+            Intrinsic::Abs
+                | Intrinsic::Sqrt
+                | Intrinsic::Int
+                | Intrinsic::Sin
+                | Intrinsic::Cos
+                | Intrinsic::Log
+                | Intrinsic::Exp
+                | Intrinsic::Rnd => 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UnaryOp {
+    Plus,
+    Minus,
+    Not,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    And,
+    Or,
+}
+
+impl BinaryOp {
+    pub fn is_arithmetic(self) -> bool {
+        matches!(self, BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod | BinaryOp::Pow)
+    }
+}
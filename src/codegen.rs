@@ -0,0 +1,1029 @@
+use std::collections::{HashMap, HashSet};
+use crate::ast;
+use crate::emitter;
+use crate::types::{Type, Types};
+
+pub const GOSUB_STACK_SIZE: usize = 256;
+
+const BOUNDS_CHECK: &str = "\
+static int bounds_check(double index, int size, int line, int column){
+if (index<0 || index>=size) {
+fprintf(stderr, \"Runtime error: index %.2f out of range 0..%d at %d:%d\\n\", index, size-1, line, column);
+exit(1);
+}
+return (int)index;
+}
+";
+
+const MOD_CHECK: &str = "\
+static long long mod_check(long long divisor){
+if (divisor==0) {
+fprintf(stderr, \"Runtime error: MOD by zero\\n\");
+exit(1);
+}
+return divisor;
+}
+";
+
+/// Strings are fixed-size values, so they can be copied, returned and
+/// concatenated without any allocation; text past `STRING_MAX` is cut off.
+const STRING_TYPE: &str = "\
+#define STRING_MAX 255
+typedef struct { char text[STRING_MAX+1]; } string_t;
+";
+
+const STRING_FROM: &str = "\
+static string_t string_from(const char *text){
+string_t s = {0};
+size_t len = 0;
+while (text[len] && len<STRING_MAX) {
+s.text[len] = text[len];
+len++;
+}
+return s;
+}
+";
+
+const STRING_CONCAT: &str = "\
+static string_t string_concat(string_t a, string_t b){
+size_t len = strlen(a.text);
+size_t i = 0;
+while (b.text[i] && len<STRING_MAX) {
+a.text[len++] = b.text[i++];
+}
+a.text[len] = '\\0';
+return a;
+}
+";
+
+const STRING_EQUAL: &str = "\
+static int string_equal(string_t a, string_t b){
+return strcmp(a.text, b.text)==0;
+}
+";
+
+/// After another value on the same line, the blanks that separate them are
+/// skipped too.
+const STRING_INPUT: &str = "\
+static string_t string_input(int skip_blanks){
+string_t s = {0};
+size_t len = 0;
+int c = getchar();
+while (c=='\\n' || c=='\\r' || (skip_blanks && (c==' ' || c=='\\t'))) {
+c = getchar();
+}
+while (c!=EOF && c!='\\n') {
+if (c!='\\r' && len<STRING_MAX) {
+s.text[len++] = (char)c;
+}
+c = getchar();
+}
+return s;
+}
+";
+
+/// Tab zones are 14 columns wide. The column is only tracked in programs
+/// that print with `,`, where every PRINT adds what it wrote.
+const PRINT_ZONE_RUNTIME: &str = "\
+static int print_column = 0;
+static void print_zone(void){
+do {
+putchar(' ');
+print_column++;
+} while (print_column%14);
+}
+";
+
+/// Drops the rest of a line that did not parse, or stops the program when
+/// there is nothing left to read.
+const INPUT_RETRY_RUNTIME: &str = "\
+static void input_retry(void){
+if (feof(stdin)) {
+fprintf(stderr, \"Runtime error: unexpected end of input\\n\");
+exit(1);
+}
+scanf(\"%*[^\\n]\");
+printf(\"?Redo from start\\n\");
+}
+";
+
+/// A xorshift generator with a fixed default seed, so RND gives the same
+/// sequence on every run until RANDOMIZE picks another one.
+const RND_RUNTIME: &str = "\
+static uint32_t rnd_state = 2463534242u;
+static void rnd_seed(double seed){
+rnd_state = (uint32_t)(long long)seed ^ 2463534242u;
+if (rnd_state==0) {
+rnd_state = 2463534242u;
+}
+}
+static double rnd(double range){
+rnd_state ^= rnd_state<<13;
+rnd_state ^= rnd_state>>17;
+rnd_state ^= rnd_state<<5;
+return range*(double)(rnd_state>>8)/16777216.0;
+}
+";
+
+/// What a generated program does when INPUT reads something that is not a
+/// number.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, clap::ValueEnum)]
+pub enum InvalidInput {
+    /// Store 0 and skip the offending word.
+    #[default]
+    Zero,
+    /// Print the prompt again and read every value of the INPUT anew.
+    Retry,
+    /// Stop with a runtime error.
+    Abort,
+}
+
+/// The TO or STEP value of a FOR loop as the loop header refers to it.
+pub enum LoopValue<'e> {
+    Literal(&'e ast::Expression),
+    Temporary(String),
+}
+
+pub struct CodeGenerator {
+    pub emitter: emitter::Emitter,
+    pub declared: HashSet<String>,
+    pub arrays: HashMap<String, Vec<usize>>,
+    pub includes: Vec<&'static str>,
+    pub link_flags: Vec<&'static str>,
+    pub helpers: Vec<&'static str>,
+    pub types: Types,
+    pub scope: Option<String>,
+    pub bounds_checks: bool,
+    pub print_zones: bool,
+    pub invalid_input: InvalidInput,
+    pub gosub_sites: usize,
+    pub for_sites: usize,
+    pub uses_gosub: bool,
+}
+
+impl CodeGenerator {
+
+    pub fn new(emitter: emitter::Emitter) -> Self {
+        CodeGenerator {
+            emitter,
+            declared: HashSet::new(),
+            arrays: HashMap::new(),
+            includes: Vec::new(),
+            link_flags: Vec::new(),
+            helpers: Vec::new(),
+            types: Types::default(),
+            scope: None,
+            bounds_checks: false,
+            print_zones: false,
+            invalid_input: InvalidInput::default(),
+            gosub_sites: 0,
+            for_sites: 0,
+            uses_gosub: false,
+        }
+    }
+
+    pub fn generate(&mut self, program: &ast::Program) {
+        self.types = Types::infer(program);
+        self.print_zones = uses_print_zones(&program.statements)
+            || program.functions.iter().any(|function| uses_print_zones(&function.body));
+        self.include("stdio.h");
+
+        for function in program.functions.iter() {
+            self.emitter.header(&signature(function, &self.types));
+            self.emitter.header_line(";");
+        }
+        for function in program.functions.iter() {
+            self.function(function);
+        }
+
+        self.emitter.header_line("int main(void){");
+
+        for statement in program.statements.iter() {
+            self.statement(statement);
+        }
+
+        self.emitter.emit_line("return 0;");
+        if self.uses_gosub {
+            self.gosub_dispatch();
+        }
+        self.emitter.emit_line("}");
+
+        if !self.link_flags.is_empty() {
+            self.emitter.prelude_line(&format!("/* link with {} */", self.link_flags.join(" ")));
+        }
+        for include in self.includes.iter() {
+            self.emitter.prelude_line(&format!("#include <{include}>"));
+        }
+        for helper in self.helpers.iter() {
+            self.emitter.prelude(helper);
+        }
+    }
+
+    pub fn include(&mut self, header: &'static str) {
+        if !self.includes.contains(&header) {
+            self.includes.push(header);
+        }
+    }
+
+    pub fn link(&mut self, flag: &'static str) {
+        if !self.link_flags.contains(&flag) {
+            self.link_flags.push(flag);
+        }
+    }
+
+    /// The math functions live in libm, which C compilers do not always link
+    /// by default.
+    pub fn use_math(&mut self) {
+        self.include("math.h");
+        self.link("-lm");
+    }
+
+    pub fn use_rnd(&mut self) {
+        self.include("stdint.h");
+        self.helper(RND_RUNTIME);
+    }
+
+    pub fn helper(&mut self, code: &'static str) {
+        if !self.helpers.contains(&code) {
+            self.helpers.push(code);
+        }
+    }
+
+    /// Each FUNCTION is generated on its own so its locals are declared at
+    /// the top of its body rather than in `main`.
+    pub fn function(&mut self, function: &ast::Function) {
+        let mut generator = CodeGenerator::new(emitter::Emitter::new());
+        generator.bounds_checks = self.bounds_checks;
+        generator.print_zones = self.print_zones;
+        generator.invalid_input = self.invalid_input;
+        generator.types = self.types.clone();
+        generator.scope = Some(function.name.clone());
+        generator.declared.extend(function.params.iter().cloned());
+
+        generator.emitter.header(&signature(function, &self.types));
+        generator.emitter.header_line("{");
+        generator.block(&function.body);
+        generator.emitter.emit_line("return 0;");
+        generator.emitter.emit_line("}");
+
+        self.emitter.header(&generator.emitter.into_string());
+        for include in generator.includes {
+            self.include(include);
+        }
+        for flag in generator.link_flags {
+            self.link(flag);
+        }
+        for helper in generator.helpers {
+            self.helper(helper);
+        }
+    }
+
+    /// GOSUB pushes the id of its call site; RETURN pops it here and jumps
+    /// back to the label emitted right after that call site.
+    pub fn gosub_dispatch(&mut self) {
+        self.emitter.emit_line("gosub_dispatch:");
+        self.emitter.emit_line("switch (gosub_stack[--gosub_top]) {");
+        for site in 0..self.gosub_sites {
+            self.emitter.emit_line(&format!("case {site}: goto gosub_return_{site};"));
+        }
+        self.emitter.emit_line("}");
+        self.emitter.emit_line("return 1;");
+    }
+
+    pub fn declare_gosub_stack(&mut self) {
+        if !self.uses_gosub {
+            self.uses_gosub = true;
+            self.emitter.header_line(&format!("int gosub_stack[{GOSUB_STACK_SIZE}];"));
+            self.emitter.header_line("int gosub_top = 0;");
+        }
+    }
+
+    pub fn runtime_error(&mut self, condition: &str, message: &str) {
+        self.emitter.emit_line(&format!("if ({condition}) {{"));
+        self.emitter.emit_line(&format!("fprintf(stderr, \"Runtime error: {message}\\n\");"));
+        self.emitter.emit_line("return 1;");
+        self.emitter.emit_line("}");
+    }
+
+    pub fn declare(&mut self, name: &str) {
+        if self.declared.insert(name.to_string()) {
+            match self.types.variable(self.scope.as_deref(), name) {
+                Type::Text => {
+                    self.use_strings();
+                    self.emitter.header("string_t ");
+                    self.emitter.header(&variable_name(name));
+                    self.emitter.header_line(" = {0};");
+                },
+                numeric => {
+                    self.emitter.header(c_type(numeric));
+                    self.emitter.header(" ");
+                    self.emitter.header(&variable_name(name));
+                    self.emitter.header_line(";");
+                },
+            }
+        }
+    }
+
+    pub fn type_of(&self, expression: &ast::Expression) -> Type {
+        self.types.of(self.scope.as_deref(), expression)
+    }
+
+    pub fn use_strings(&mut self) {
+        self.helper(STRING_TYPE);
+    }
+
+    /// Each string helper is emitted only once something calls it, so the
+    /// generated C has no unused functions.
+    pub fn use_string_helper(&mut self, code: &'static str) {
+        self.use_strings();
+        if code == STRING_CONCAT || code == STRING_EQUAL {
+            self.include("string.h");
+        }
+        self.helper(code);
+    }
+
+    pub fn statement(&mut self, statement: &ast::Statement) {
+        match statement {
+            ast::Statement::Print { items, newline, format } => self.print(items, *newline, format.as_ref()),
+            ast::Statement::If { condition, body, else_ifs, else_body } => {
+                self.emitter.emit("if (");
+                self.expression(condition);
+                self.emitter.emit_line(") {");
+                self.block(body);
+
+                for else_if in else_ifs.iter() {
+                    self.emitter.emit("} else if (");
+                    self.expression(&else_if.condition);
+                    self.emitter.emit_line(") {");
+                    self.block(&else_if.body);
+                }
+
+                if let Some(else_body) = else_body {
+                    self.emitter.emit_line("} else {");
+                    self.block(else_body);
+                }
+                self.emitter.emit_line("}");
+            },
+            ast::Statement::While { condition, body } => {
+                self.emitter.emit("while (");
+                self.expression(condition);
+                self.emitter.emit_line(") {");
+                self.block(body);
+                self.emitter.emit_line("}");
+            },
+            ast::Statement::For { variable, start, end, step, body } => {
+                self.declare(variable);
+                let end = self.loop_value("end", end);
+                let step = step.as_ref().map(|step| self.loop_value("step", step));
+                self.for_sites += 1;
+
+                let counter = variable_name(variable);
+                self.emitter.emit("for (");
+                self.emitter.emit(&counter);
+                self.emitter.emit(" = ");
+                self.expression(start);
+                self.emitter.emit("; ");
+                self.for_condition(&counter, &end, step.as_ref());
+                self.emitter.emit("; ");
+                self.emitter.emit(&counter);
+                self.emitter.emit(" = ");
+                self.emitter.emit(&counter);
+                self.for_increment(step.as_ref());
+                self.emitter.emit_line(") {");
+                self.block(body);
+                self.emitter.emit_line("}");
+            },
+            ast::Statement::Label(name) => {
+                self.emitter.emit(&label_name(name));
+                self.emitter.emit_line(":");
+            },
+            ast::Statement::Goto(name) => {
+                self.emitter.emit("goto ");
+                self.emitter.emit(&label_name(name));
+                self.emitter.emit_line(";");
+            },
+            ast::Statement::Gosub { label, line } => {
+                self.declare_gosub_stack();
+                let site = self.gosub_sites;
+                self.gosub_sites += 1;
+
+                self.runtime_error(
+                    &format!("gosub_top == {GOSUB_STACK_SIZE}"),
+                    &format!("GOSUB nested too deeply at line {line}"),
+                );
+                self.emitter.emit_line(&format!("gosub_stack[gosub_top++] = {site};"));
+                self.emitter.emit_line(&format!("goto {};", label_name(label)));
+                self.emitter.emit_line(&format!("gosub_return_{site}:;"));
+            },
+            ast::Statement::Return { value: Some(value), .. } => {
+                self.emitter.emit("return ");
+                self.expression(value);
+                self.emitter.emit_line(";");
+            },
+            ast::Statement::Return { line, value: None } => {
+                self.declare_gosub_stack();
+                self.runtime_error("gosub_top == 0", &format!("RETURN without GOSUB at line {line}"));
+                self.emitter.emit_line("goto gosub_dispatch;");
+            },
+            ast::Statement::Let { name, value } => {
+                self.declare(name);
+                self.emitter.emit(&variable_name(name));
+                self.emitter.emit(" = ");
+                self.expression(value);
+                self.emitter.emit_line(";");
+            },
+            ast::Statement::Dim { name, bounds, .. } if bounds.is_empty() => {
+                self.declare(name);
+                self.emitter.emit(&variable_name(name));
+                self.emitter.emit_line(" = 0;");
+            },
+            ast::Statement::Dim { name, bounds, .. } => {
+                // Static storage keeps large arrays in `main` off the stack. A
+                // FUNCTION gets fresh arrays on every call, so recursive calls
+                // do not share them.
+                let element_type = self.types.array(self.scope.as_deref(), name);
+                if self.scope.is_none() {
+                    self.emitter.header("static ");
+                }
+                self.emitter.header(c_type(element_type));
+                self.emitter.header(" ");
+                self.emitter.header(&array_name(name));
+                for bound in bounds.iter() {
+                    self.emitter.header(&format!("[{}]", bound + 1));
+                }
+                self.emitter.header_line(" = {0};");
+                self.arrays.insert(name.clone(), bounds.clone());
+            },
+            ast::Statement::SetElement { element, value } => {
+                self.element(element);
+                self.emitter.emit(" = ");
+                self.expression(value);
+                self.emitter.emit_line(";");
+            },
+            ast::Statement::Randomize(seed) => {
+                self.use_rnd();
+                self.emitter.emit("rnd_seed(");
+                self.expression(seed);
+                self.emitter.emit_line(");");
+            },
+            ast::Statement::Input { prompt, names } => self.input(prompt.as_deref(), names),
+        }
+    }
+
+    /// With `InvalidInput::Retry` the whole statement, prompt included,
+    /// starts over when a number does not parse.
+    pub fn input(&mut self, prompt: Option<&str>, names: &[String]) {
+        for name in names.iter() {
+            self.declare(name);
+        }
+
+        let retry = self.invalid_input == InvalidInput::Retry;
+        if retry {
+            self.include("stdlib.h");
+            self.helper(INPUT_RETRY_RUNTIME);
+            self.emitter.emit_line("for (;;) {");
+        }
+        // With tab zones the prompt counts towards the column, and the Enter
+        // that ends the input takes the terminal back to column 0.
+        match prompt {
+            Some(prompt) if self.print_zones => {
+                self.helper(PRINT_ZONE_RUNTIME);
+                self.emitter.emit_line(&format!("print_column += printf(\"%s\", {});", c_string(prompt)));
+            },
+            Some(prompt) => self.emitter.emit_line(&format!("fputs({}, stdout);", c_string(prompt))),
+            None => (),
+        }
+        for (index, name) in names.iter().enumerate() {
+            self.input_value(name, index > 0);
+        }
+        if retry {
+            self.emitter.emit_line("break;");
+            self.emitter.emit_line("}");
+        }
+        if self.print_zones {
+            self.helper(PRINT_ZONE_RUNTIME);
+            self.emitter.emit_line("print_column = 0;");
+        }
+    }
+
+    pub fn input_value(&mut self, name: &str, after_value: bool) {
+        let conversion = match self.types.variable(self.scope.as_deref(), name) {
+            Type::Text => {
+                self.use_string_helper(STRING_INPUT);
+                self.emitter.emit(&variable_name(name));
+                self.emitter.emit_line(&format!(" = string_input({});", after_value as i32));
+                return;
+            },
+            Type::Integer => "%lld",
+            Type::Float => "%lf",
+        };
+        let variable = variable_name(name);
+
+        match self.invalid_input {
+            InvalidInput::Zero => {
+                self.emitter.emit_line(&format!("if(0 == scanf(\"{conversion}\", &{variable})) {{"));
+                self.emitter.emit_line(&format!("{variable} = 0;"));
+                self.emitter.emit_line("scanf(\"%*s\");");
+            },
+            InvalidInput::Retry => {
+                self.emitter.emit_line(&format!("if (1 != scanf(\"{conversion}\", &{variable})) {{"));
+                self.emitter.emit_line("input_retry();");
+                self.emitter.emit_line("continue;");
+            },
+            InvalidInput::Abort => {
+                self.include("stdlib.h");
+                self.emitter.emit_line(&format!("if (1 != scanf(\"{conversion}\", &{variable})) {{"));
+                self.emitter.emit_line(&format!("fprintf(stderr, \"Runtime error: invalid input for {name}\\n\");"));
+                self.emitter.emit_line("exit(1);");
+            },
+        }
+        self.emitter.emit_line("}");
+    }
+
+    /// Each run of items between tab zones becomes a single `printf`.
+    pub fn print(&mut self, items: &[ast::Printable], newline: bool, using: Option<&ast::Format>) {
+        if self.print_zones {
+            self.helper(PRINT_ZONE_RUNTIME);
+        }
+
+        let segments = items.split(|item| *item == ast::Printable::Zone).collect::<Vec<_>>();
+        for (index, segment) in segments.iter().enumerate() {
+            if index > 0 {
+                self.emitter.emit_line("print_zone();");
+            }
+
+            let end = match newline && index == segments.len() - 1 {
+                true => "\n",
+                false => "",
+            };
+            if !segment.is_empty() || !end.is_empty() {
+                self.printf(segment, end, using);
+            }
+        }
+
+        if newline && self.print_zones {
+            self.emitter.emit_line("print_column = 0;");
+        }
+    }
+
+    /// Text is always passed as a `%s` argument, never as part of the format.
+    /// With PRINT USING every value is formatted as a float by the given
+    /// format.
+    pub fn printf(&mut self, items: &[ast::Printable], end: &str, using: Option<&ast::Format>) {
+        let has_values = items.iter().any(|item| matches!(item, ast::Printable::Expr(_)));
+
+        // `fputs` does not say how much it wrote, so tab zones need `printf`.
+        if !has_values && !self.print_zones {
+            let text = items.iter()
+                .map(|item| match item {
+                    ast::Printable::Text(text) => text.as_str(),
+                    _ => "",
+                })
+                .collect::<String>();
+            self.emitter.emit_line(&format!("fputs({}, stdout);", c_string(&(text + end))));
+            return;
+        }
+
+        let format = items.iter()
+            .map(|item| match (item, using) {
+                (ast::Printable::Text(_), _) => String::from("%s"),
+                (ast::Printable::Expr(_), Some(using)) => using_specifier(using),
+                (ast::Printable::Expr(expression), None) => print_format(self.type_of(expression)).to_string(),
+                (ast::Printable::Zone, _) => String::new(),
+            })
+            .collect::<String>();
+
+        if self.print_zones {
+            self.emitter.emit("print_column += ");
+        }
+        self.emitter.emit(&format!("printf({}", c_string(&(format + end))));
+
+        for item in items.iter() {
+            if let ast::Printable::Text(text) = item {
+                self.emitter.emit(", ");
+                self.emitter.emit(&c_string(text));
+            }
+            if let ast::Printable::Expr(expression) = item {
+                self.emitter.emit(", ");
+                let value = match using {
+                    Some(_) => Type::Float,
+                    None => self.type_of(expression),
+                };
+                match value {
+                    Type::Text => {
+                        self.expression(expression);
+                        self.emitter.emit(".text");
+                    },
+                    numeric => {
+                        self.emitter.emit(&format!("({})(", c_type(numeric)));
+                        self.expression(expression);
+                        self.emitter.emit(")");
+                    },
+                }
+            }
+        }
+        self.emitter.emit_line(");");
+    }
+
+    /// TO and STEP are evaluated once, before the loop starts. Anything but
+    /// a literal is stored in a temporary so the loop body cannot change it.
+    pub fn loop_value<'e>(&mut self, kind: &str, value: &'e ast::Expression) -> LoopValue<'e> {
+        if literal_sign(value).is_some() {
+            return LoopValue::Literal(value);
+        }
+
+        let name = format!("for_{kind}_{}", self.for_sites);
+        let value_type = self.type_of(value);
+        self.emitter.header_line(&format!("{} {name};", c_type(value_type)));
+
+        self.emitter.emit(&format!("{name} = "));
+        self.expression(value);
+        self.emitter.emit_line(";");
+        LoopValue::Temporary(name)
+    }
+
+    pub fn loop_value_expression(&mut self, value: &LoopValue) {
+        match value {
+            LoopValue::Literal(expression) => self.expression(expression),
+            LoopValue::Temporary(name) => self.emitter.emit(name),
+        }
+    }
+
+    /// A literal step fixes the loop direction at compile time; any other
+    /// step picks the comparison at run time so negative steps count down.
+    pub fn for_condition(&mut self, variable: &str, end: &LoopValue, step: Option<&LoopValue>) {
+        match step {
+            Some(LoopValue::Literal(step)) if literal_sign(step) == Some(true) => self.for_bound(variable, ">=", end),
+            Some(LoopValue::Temporary(step)) => {
+                self.emitter.emit(&format!("{step}>=0 ? "));
+                self.for_bound(variable, "<=", end);
+                self.emitter.emit(" : ");
+                self.for_bound(variable, ">=", end);
+            },
+            _ => self.for_bound(variable, "<=", end),
+        }
+    }
+
+    pub fn for_bound(&mut self, variable: &str, comparison: &str, end: &LoopValue) {
+        self.emitter.emit(variable);
+        self.emitter.emit(comparison);
+        self.loop_value_expression(end);
+    }
+
+    pub fn for_increment(&mut self, step: Option<&LoopValue>) {
+        match step {
+            None => self.emitter.emit("+1"),
+            Some(LoopValue::Literal(step @ ast::Expression::Number(_))) => {
+                self.emitter.emit("+");
+                self.expression(step);
+            },
+            Some(LoopValue::Literal(ast::Expression::Unary { op: ast::UnaryOp::Minus, operand })) if matches!(**operand, ast::Expression::Number(_)) => {
+                self.emitter.emit("-");
+                self.expression(operand);
+            },
+            Some(LoopValue::Literal(step)) => {
+                self.emitter.emit("+(");
+                self.expression(step);
+                self.emitter.emit(")");
+            },
+            Some(LoopValue::Temporary(step)) => {
+                self.emitter.emit("+");
+                self.emitter.emit(step);
+            },
+        }
+    }
+
+    /// With bounds checks on, every index goes through a helper that reports
+    /// the source location and exits when it is out of range.
+    pub fn element(&mut self, element: &ast::Element) {
+        let bounds = self.arrays.get(&element.array).cloned().unwrap_or_default();
+
+        self.emitter.emit(&array_name(&element.array));
+        for (index, bound) in element.indices.iter().zip(bounds) {
+            if self.bounds_checks {
+                self.include("stdlib.h");
+                self.helper(BOUNDS_CHECK);
+                self.emitter.emit("[bounds_check(");
+                self.expression(index);
+                self.emitter.emit(&format!(", {}, {}, {})]", bound + 1, element.span.line, element.span.column));
+            } else {
+                self.emitter.emit("[(int)(");
+                self.expression(index);
+                self.emitter.emit(")]");
+            }
+        }
+    }
+
+    /// `&&` already binds tighter than `||`; the parentheses only spell that
+    /// out, as C compilers warn without them.
+    pub fn or_operand(&mut self, operand: &ast::Expression) {
+        if let ast::Expression::Binary { op: ast::BinaryOp::And, .. } = operand {
+            self.emitter.emit("(");
+            self.expression(operand);
+            self.emitter.emit(")");
+        } else {
+            self.expression(operand);
+        }
+    }
+
+    pub fn arguments(&mut self, args: &[ast::Expression]) {
+        self.emitter.emit("(");
+        for (index, arg) in args.iter().enumerate() {
+            if index > 0 {
+                self.emitter.emit(", ");
+            }
+            self.expression(arg);
+        }
+        self.emitter.emit(")");
+    }
+
+    pub fn block(&mut self, body: &[ast::Statement]) {
+        for statement in body.iter() {
+            self.statement(statement);
+        }
+    }
+
+    pub fn expression(&mut self, expression: &ast::Expression) {
+        match expression {
+            // A literal too large for an integer is a float, so C must read
+            // it as one too.
+            ast::Expression::Number(text) if self.type_of(expression) == Type::Float && !text.contains('.') => {
+                self.emitter.emit(text);
+                self.emitter.emit(".0");
+            },
+            ast::Expression::Number(text) => self.emitter.emit(text),
+            ast::Expression::Text(text) => {
+                self.use_string_helper(STRING_FROM);
+                self.emitter.emit("string_from(");
+                self.emitter.emit(&c_string(text));
+                self.emitter.emit(")");
+            },
+            ast::Expression::Variable(name) => self.emitter.emit(&variable_name(name)),
+            ast::Expression::Element(element) => self.element(element),
+            ast::Expression::Call { name, args } => {
+                self.emitter.emit(&function_name(name));
+                self.arguments(args);
+            },
+            ast::Expression::Intrinsic { function: ast::Intrinsic::Abs, args } if self.type_of(expression) == Type::Integer => {
+                self.include("stdlib.h");
+                self.emitter.emit("llabs");
+                self.arguments(args);
+            },
+            ast::Expression::Intrinsic { function, args } => {
+                match function {
+                    ast::Intrinsic::Rnd => self.use_rnd(),
+                    _ => self.use_math(),
+                }
+                self.emitter.emit(intrinsic_name(*function));
+                self.arguments(args);
+            },
+            ast::Expression::Unary { op: ast::UnaryOp::Not, operand } if matches!(**operand, ast::Expression::Group(_)) => {
+                self.emitter.emit("!");
+                self.expression(operand);
+            },
+            ast::Expression::Unary { op: ast::UnaryOp::Not, operand } => {
+                // `!` binds tighter than the comparison it negates.
+                self.emitter.emit("!(");
+                self.expression(operand);
+                self.emitter.emit(")");
+            },
+            ast::Expression::Unary { op, operand } => {
+                self.operator(unary_operator(*op), operand);
+                self.expression(operand);
+            },
+            ast::Expression::Binary { op: op @ (ast::BinaryOp::Add | ast::BinaryOp::Eq | ast::BinaryOp::NotEq), lhs, rhs } if lhs.is_text() => {
+                let helper = match op {
+                    ast::BinaryOp::Add => STRING_CONCAT,
+                    _ => STRING_EQUAL,
+                };
+                self.use_string_helper(helper);
+                self.emitter.emit(match op {
+                    ast::BinaryOp::Add => "string_concat(",
+                    ast::BinaryOp::NotEq => "!string_equal(",
+                    _ => "string_equal(",
+                });
+                self.expression(lhs);
+                self.emitter.emit(", ");
+                self.expression(rhs);
+                self.emitter.emit(")");
+            },
+            ast::Expression::Group(inner) => {
+                self.emitter.emit("(");
+                self.expression(inner);
+                self.emitter.emit(")");
+            },
+            ast::Expression::Binary { op: ast::BinaryOp::Mod, lhs, rhs } if self.type_of(expression) == Type::Integer => {
+                // `%` by zero crashes the program, so any divisor other than a
+                // nonzero literal is checked first.
+                self.expression(lhs);
+                self.emitter.emit("%");
+                match **rhs {
+                    ast::Expression::Number(ref text) if text.parse::<i64>().is_ok_and(|divisor| divisor != 0) => self.expression(rhs),
+                    _ => {
+                        self.include("stdlib.h");
+                        self.helper(MOD_CHECK);
+                        self.emitter.emit("mod_check(");
+                        self.expression(rhs);
+                        self.emitter.emit(")");
+                    },
+                }
+            },
+            ast::Expression::Binary { op: ast::BinaryOp::Div, lhs, rhs } if self.type_of(lhs) == Type::Integer && self.type_of(rhs) == Type::Integer => {
+                // Dividing two integers still gives a float, as it did before
+                // integers existed.
+                self.expression(lhs);
+                self.emitter.emit("/(double)");
+                self.expression(rhs);
+            },
+            ast::Expression::Binary { op: op @ (ast::BinaryOp::Mod | ast::BinaryOp::Pow), lhs, rhs } => {
+                self.use_math();
+                self.emitter.emit(match op {
+                    ast::BinaryOp::Mod => "fmod(",
+                    _ => "pow(",
+                });
+                self.expression(lhs);
+                self.emitter.emit(", ");
+                self.expression(rhs);
+                self.emitter.emit(")");
+            },
+            ast::Expression::Binary { op: ast::BinaryOp::Or, lhs, rhs } => {
+                self.or_operand(lhs);
+                self.emitter.emit("||");
+                self.or_operand(rhs);
+            },
+            ast::Expression::Binary { op, lhs, rhs } => {
+                self.expression(lhs);
+                self.operator(binary_operator(*op), rhs);
+                self.expression(rhs);
+            },
+        }
+    }
+
+    /// Keeps `5 - -3` from coming out as `5--3`, which C reads as a
+    /// decrement.
+    pub fn operator(&mut self, operator: &str, operand: &ast::Expression) {
+        self.emitter.emit(operator);
+        if leading_sign(operand).is_some_and(|sign| operator.ends_with(sign)) {
+            self.emitter.emit(" ");
+        }
+    }
+}
+
+/// `name$` is not a valid C identifier, so string variables become
+/// `name_str` and numeric ones `name_num`. The suffix also keeps a variable
+/// from shadowing a C keyword or library function such as `sqrt`.
+fn variable_name(name: &str) -> String {
+    match name.strip_suffix('$') {
+        Some(base) => format!("{base}_str"),
+        None => format!("{name}_num"),
+    }
+}
+
+/// Suffixed for the same reason as [`variable_name`].
+fn array_name(name: &str) -> String {
+    format!("{name}_arr")
+}
+
+/// Prefixed so a label can be named after a C keyword, such as `while`.
+fn label_name(name: &str) -> String {
+    match name.strip_suffix('$') {
+        Some(base) => format!("lbl_{base}_str"),
+        None => format!("lbl_{name}"),
+    }
+}
+
+/// Prefixed so a FUNCTION can share its name with a variable or a C library
+/// function.
+fn function_name(name: &str) -> String {
+    format!("fn_{name}")
+}
+
+fn signature(function: &ast::Function, types: &Types) -> String {
+    let scope = Some(function.name.as_str());
+    let params = match function.params.is_empty() {
+        true => String::from("void"),
+        false => function.params.iter()
+            .map(|param| format!("{} {}", c_type(types.variable(scope, param)), variable_name(param)))
+            .collect::<Vec<_>>()
+            .join(", "),
+    };
+    format!("{} {}({params})", c_type(types.returns(&function.name)), function_name(&function.name))
+}
+
+fn c_type(numeric: Type) -> &'static str {
+    match numeric {
+        Type::Integer => "long long",
+        Type::Float | Type::Text => "double",
+    }
+}
+
+/// Spells out `text` as a C string literal. `??` is split up as well, since
+/// some C compilers still read it as the start of a trigraph.
+fn c_string(text: &str) -> String {
+    let mut literal = String::from("\"");
+    let mut previous = '\0';
+
+    for c in text.chars() {
+        match c {
+            '"' => literal += "\\\"",
+            '\\' => literal += "\\\\",
+            '\n' => literal += "\\n",
+            '\t' => literal += "\\t",
+            '?' if previous == '?' => literal += "\\?",
+            c if c.is_ascii_control() => literal += &format!("\\{:03o}", c as u32),
+            c => literal.push(c),
+        }
+        previous = c;
+    }
+
+    literal.push('"');
+    literal
+}
+
+fn print_format(value: Type) -> &'static str {
+    match value {
+        Type::Integer => "%lld",
+        Type::Float => "%.2f",
+        Type::Text => "%s",
+    }
+}
+
+fn using_specifier(format: &ast::Format) -> String {
+    let sign = if format.sign { "+" } else { "" };
+    let zeros = if format.zeros { "0" } else { "" };
+    format!("%{sign}{zeros}{}.{}f", format.width, format.precision)
+}
+
+fn uses_print_zones(statements: &[ast::Statement]) -> bool {
+    statements.iter().any(|statement| match statement {
+        ast::Statement::Print { items, .. } => items.contains(&ast::Printable::Zone),
+        ast::Statement::If { body, else_ifs, else_body, .. } => {
+            uses_print_zones(body)
+                || else_ifs.iter().any(|else_if| uses_print_zones(&else_if.body))
+                || else_body.as_deref().is_some_and(uses_print_zones)
+        },
+        ast::Statement::While { body, .. } | ast::Statement::For { body, .. } => uses_print_zones(body),
+        _ => false,
+    })
+}
+
+fn intrinsic_name(function: ast::Intrinsic) -> &'static str {
+    match function {
+        ast::Intrinsic::Abs => "fabs",
+        ast::Intrinsic::Sqrt => "sqrt",
+        ast::Intrinsic::Int => "(long long)floor",
+        ast::Intrinsic::Sin => "sin",
+        ast::Intrinsic::Cos => "cos",
+        ast::Intrinsic::Log => "log",
+        ast::Intrinsic::Exp => "exp",
+        ast::Intrinsic::Rnd => "rnd",
+    }
+}
+
+fn unary_operator(op: ast::UnaryOp) -> &'static str {
+    match op {
+        ast::UnaryOp::Plus => "+",
+        ast::UnaryOp::Minus => "-",
+        ast::UnaryOp::Not => "!",
+    }
+}
+
+/// The sign the C for `expression` may start with.
+fn leading_sign(expression: &ast::Expression) -> Option<&'static str> {
+    match expression {
+        ast::Expression::Unary { op: op @ (ast::UnaryOp::Plus | ast::UnaryOp::Minus), .. } => Some(unary_operator(*op)),
+        ast::Expression::Binary { lhs, .. } => leading_sign(lhs),
+        _ => None,
+    }
+}
+
+fn binary_operator(op: ast::BinaryOp) -> &'static str {
+    match op {
+        ast::BinaryOp::Add => "+",
+        ast::BinaryOp::Sub => "-",
+        ast::BinaryOp::Mul => "*",
+        ast::BinaryOp::Div => "/",
+        ast::BinaryOp::Mod => "%",
+        ast::BinaryOp::Pow => "^",
+        ast::BinaryOp::Eq => "==",
+        ast::BinaryOp::NotEq => "!=",
+        ast::BinaryOp::Lt => "<",
+        ast::BinaryOp::LtEq => "<=",
+        ast::BinaryOp::Gt => ">",
+        ast::BinaryOp::GtEq => ">=",
+        ast::BinaryOp::And => "&&",
+        ast::BinaryOp::Or => "||",
+    }
+}
+
+/// Returns `Some(is_negative)` for numeric literals, `None` for anything
+/// whose sign is only known at run time.
+fn literal_sign(expression: &ast::Expression) -> Option<bool> {
+    match expression {
+        ast::Expression::Number(_) => Some(false),
+        ast::Expression::Unary { op: ast::UnaryOp::Plus, operand } => literal_sign(operand),
+        ast::Expression::Group(inner) => literal_sign(inner),
+        ast::Expression::Unary { op: ast::UnaryOp::Minus, operand } => literal_sign(operand).map(|negative| !negative),
+        _ => None,
+    }
+}
@@ -0,0 +1,153 @@
+use std::fmt;
+use crate::error::CompileError;
+use crate::lex::Span;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Diagnostic {
+    pub kind: &'static str,
+    pub message: String,
+    pub span: Option<Span>,
+    pub notes: Vec<String>,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+
+    pub fn render(&self, filename: &str, source: &str) -> String {
+        let mut out = format!("error[{}]: {}\n", self.kind, self.message);
+
+        let span = match self.span {
+            Some(span) => span,
+            None => {
+                self.render_footer(&mut out, "");
+                return out;
+            },
+        };
+
+        let line = source.lines().nth(span.line.saturating_sub(1)).unwrap_or("");
+        let gutter = " ".repeat(span.line.to_string().len());
+
+        out += &format!("{gutter}--> {filename}:{}:{}\n", span.line, span.column);
+        out += &format!("{gutter} |\n");
+        out += &format!("{} | {}\n", span.line, line);
+
+        // Keep tabs in the padding so the caret lines up with the source line.
+        let padding = line.chars()
+            .take(span.column.saturating_sub(1))
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+
+        let end = (span.offset + span.length).min(source.len());
+        let underlined = source.get(span.offset..end).unwrap_or("");
+        let underlined = underlined.lines().next().unwrap_or("");
+        let width = underlined.chars().count().max(1);
+
+        out += &format!("{gutter} | {padding}{}\n", "^".repeat(width));
+        if !self.notes.is_empty() || self.help.is_some() {
+            out += &format!("{gutter} |\n");
+        }
+        self.render_footer(&mut out, &gutter);
+        out
+    }
+
+    fn render_footer(&self, out: &mut String, gutter: &str) {
+        for note in self.notes.iter() {
+            *out += &format!("{gutter} = note: {note}\n");
+        }
+
+        if let Some(help) = &self.help {
+            *out += &format!("{gutter} = help: {help}\n");
+        }
+    }
+}
+
+impl From<&CompileError> for Diagnostic {
+    fn from(error: &CompileError) -> Self {
+        match error {
+            CompileError::Lexical { message, span } => Diagnostic {
+                kind: "lexical",
+                message: message.clone(),
+                span: Some(*span),
+                notes: Vec::new(),
+                help: None,
+            },
+            CompileError::Syntax { message, span } => Diagnostic {
+                kind: "syntax",
+                message: message.clone(),
+                span: Some(*span),
+                notes: Vec::new(),
+                help: None,
+            },
+            CompileError::Semantic { message, span, notes, help } => Diagnostic {
+                kind: "semantic",
+                message: message.clone(),
+                span: Some(*span),
+                notes: notes.clone(),
+                help: help.clone(),
+            },
+            CompileError::Io(message) => Diagnostic {
+                kind: "io",
+                message: message.clone(),
+                span: None,
+                notes: Vec::new(),
+                help: None,
+            },
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Diagnostics {
+    pub errors: Vec<CompileError>,
+}
+
+impl Diagnostics {
+
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn render(&self, filename: &str, source: &str) -> String {
+        let mut out = String::new();
+
+        for error in self.errors.iter() {
+            out += &Diagnostic::from(error).render(filename, source);
+            out += "\n";
+        }
+
+        match self.errors.len() {
+            0 => (),
+            1 => out += "error: aborting due to previous error\n",
+            count => out += &format!("error: aborting due to {count} previous errors\n"),
+        }
+
+        out
+    }
+}
+
+impl From<Vec<CompileError>> for Diagnostics {
+    fn from(errors: Vec<CompileError>) -> Self {
+        Diagnostics { errors }
+    }
+}
+
+impl From<CompileError> for Diagnostics {
+    fn from(error: CompileError) -> Self {
+        Diagnostics { errors: vec![error] }
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for error in self.errors.iter() {
+            writeln!(f, "{error}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Diagnostics {}
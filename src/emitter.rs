@@ -1,24 +1,25 @@
 use std::fs::File;
 use std::io::prelude::*;
-use std::panic;
-use std::path::PathBuf;
+use std::path::Path;
+use crate::error::CompileError;
 
-#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
 pub struct Emitter {
-    pub full_path: std::path::PathBuf,
+    pub prelude: String,
     pub header: String,
     pub code: String,
 }
 
 impl Emitter {
 
-    pub fn new(full_path: PathBuf) -> Self {
+    pub fn new() -> Self {
 
+        let prelude = "".to_string();
         let header = "".to_string();
         let code = "".to_string();
 
         Emitter {
-            full_path,
+            prelude,
             header,
             code,
         }
@@ -33,6 +34,17 @@ impl Emitter {
         self.code += "\n";
     }
 
+    /// The prelude comes before the header, for includes and runtime
+    /// helpers that are only known to be needed once the code is generated.
+    pub fn prelude(&mut self, code: &str) {
+        self.prelude += code;
+    }
+
+    pub fn prelude_line(&mut self, code: &str) {
+        self.prelude += code;
+        self.prelude += "\n";
+    }
+
     pub fn header(&mut self, code: &str) {
         self.header += code;
     }
@@ -42,21 +54,22 @@ impl Emitter {
         self.header += "\n";
     }
 
-    #[allow(dead_code)]
-    pub fn write_file(&self) {
-        let mut f = match File::create(&self.full_path) {
-            Ok(file) => file,
-            Err(err) => panic!("[EMITTER] {err}"),
-        };
+    pub fn into_string(self) -> String {
+        self.prelude + &self.header + &self.code
+    }
+
+    pub fn write_to<W: Write>(&self, sink: &mut W) -> std::io::Result<()> {
+        sink.write_all(self.prelude.as_bytes())?;
+        sink.write_all(self.header.as_bytes())?;
+        sink.write_all(self.code.as_bytes())?;
+        sink.flush()
+    }
 
-        match f.write_all(self.header.as_bytes()) {
-            Ok(_) => (),
-            Err(err) => panic!("[EMITTER] {err}"),
-        };
+    pub fn write_file(&self, full_path: &Path) -> Result<(), CompileError> {
+        let path = full_path.display();
 
-        match f.write_all(self.code.as_bytes()) {
-            Ok(_) => (),
-            Err(err) => panic!("[EMITTER] {err}"),
-        };
+        File::create(full_path)
+            .and_then(|mut f| self.write_to(&mut f))
+            .map_err(|err| CompileError::Io(format!("{err} | {path}")))
     }
 }
@@ -0,0 +1,34 @@
+use std::fmt;
+use crate::lex::Span;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum CompileError {
+    Lexical { message: String, span: Span },
+    Syntax { message: String, span: Span },
+    Semantic { message: String, span: Span, notes: Vec<String>, help: Option<String> },
+    Io(String),
+}
+
+impl CompileError {
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            CompileError::Lexical { span, .. }
+                | CompileError::Syntax { span, .. }
+                | CompileError::Semantic { span, .. } => Some(*span),
+            CompileError::Io(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompileError::Lexical { message, span } => write!(f, "[LEXER] Error at {span}: {message}"),
+            CompileError::Syntax { message, span } => write!(f, "[PARSER] Error at {span}: {message}"),
+            CompileError::Semantic { message, span, .. } => write!(f, "[SEMANTIC] Error at {span}: {message}"),
+            CompileError::Io(message) => write!(f, "[IO] Error: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for CompileError {}
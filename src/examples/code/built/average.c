@@ -1,29 +1,28 @@
 #include <stdio.h>
 int main(void){
-float a;
-float b;
-float s;
-float c;
-a = 0;
-while (a<1) {
-printf("Enter number of scores: \n");
-if(0 == scanf("%f", &a)) {
-a = 0;
+double a_num;
+long long b_num;
+double s_num;
+double c_num;
+a_num = 0;
+while (a_num<1) {
+fputs("Enter number of scores: ", stdout);
+if(0 == scanf("%lf", &a_num)) {
+a_num = 0;
 scanf("%*s");
 }
 }
-b = 0;
-s = 0;
-printf("Enter one value at a time: \n");
-while (b<a) {
-if(0 == scanf("%f", &c)) {
-c = 0;
+b_num = 0;
+s_num = 0;
+fputs("Enter one value at a time: \n", stdout);
+while (b_num<a_num) {
+if(0 == scanf("%lf", &c_num)) {
+c_num = 0;
 scanf("%*s");
 }
-s = s+c;
-b = b+1;
+s_num = s_num+c_num;
+b_num = b_num+1;
 }
-printf("Average: \n");
-printf("%.2f\n", (float)(s/a));
+printf("%s%.2f\n", "Average: ", (double)(s_num/a_num));
 return 0;
 }
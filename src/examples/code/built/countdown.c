@@ -0,0 +1,25 @@
+#include <stdio.h>
+int main(void){
+long long i_num;
+double s_num;
+double j_num;
+double for_end_2;
+double for_step_2;
+for (i_num = 1; i_num<=3; i_num = i_num+1) {
+printf("%lld\n", (long long)(i_num));
+}
+for (i_num = 3; i_num>=1; i_num = i_num-1) {
+printf("%lld\n", (long long)(i_num));
+}
+fputs("Step size: \n", stdout);
+if(0 == scanf("%lf", &s_num)) {
+s_num = 0;
+scanf("%*s");
+}
+for_end_2 = 10*s_num;
+for_step_2 = s_num;
+for (j_num = 0; for_step_2>=0 ? j_num<=for_end_2 : j_num>=for_end_2; j_num = j_num+for_step_2) {
+printf("%.2f\n", (double)(j_num));
+}
+return 0;
+}
@@ -0,0 +1,29 @@
+/* link with -lm */
+#include <stdio.h>
+#include <stdint.h>
+#include <math.h>
+static uint32_t rnd_state = 2463534242u;
+static void rnd_seed(double seed){
+rnd_state = (uint32_t)(long long)seed ^ 2463534242u;
+if (rnd_state==0) {
+rnd_state = 2463534242u;
+}
+}
+static double rnd(double range){
+rnd_state ^= rnd_state<<13;
+rnd_state ^= rnd_state>>17;
+rnd_state ^= rnd_state<<5;
+return range*(double)(rnd_state>>8)/16777216.0;
+}
+int main(void){
+long long i_num;
+rnd_seed(42);
+for (i_num = 1; i_num<=5; i_num = i_num+1) {
+printf("%lld\n", (long long)((long long)floor(rnd(6))+1));
+}
+printf("%.2f\n", (double)(sqrt(2)));
+printf("%.2f\n", (double)(fabs(-3.5)));
+printf("%.2f\n", (double)(sin(0)+cos(0)));
+printf("%.2f\n", (double)(log(exp(1))));
+return 0;
+}
@@ -1,23 +1,23 @@
 #include <stdio.h>
 int main(void){
-float nums;
-float a;
-float b;
-float c;
-printf("How many fibonacci numbers do you want?\n");
-if(0 == scanf("%f", &nums)) {
-nums = 0;
+double nums_num;
+long long a_num;
+long long b_num;
+long long c_num;
+fputs("How many fibonacci numbers do you want?\n", stdout);
+if(0 == scanf("%lf", &nums_num)) {
+nums_num = 0;
 scanf("%*s");
 }
-printf("\n");
-a = 0;
-b = 1;
-while (nums>0) {
-printf("%.2f\n", (float)(a));
-c = a+b;
-a = b;
-b = c;
-nums = nums-1;
+fputs("\n", stdout);
+a_num = 0;
+b_num = 1;
+while (nums_num>0) {
+printf("%lld\n", (long long)(a_num));
+c_num = a_num+b_num;
+a_num = b_num;
+b_num = c_num;
+nums_num = nums_num-1;
 }
 return 0;
 }
@@ -0,0 +1,29 @@
+#include <stdio.h>
+long long fn_square(long long x_num);
+long long fn_sumsquares(long long a_num, long long b_num);
+long long fn_factorial(long long n_num);
+long long fn_square(long long x_num){
+return x_num*x_num;
+return 0;
+}
+long long fn_sumsquares(long long a_num, long long b_num){
+long long s_num;
+s_num = fn_square(a_num)+fn_square(b_num);
+return s_num;
+return 0;
+}
+long long fn_factorial(long long n_num){
+if (n_num<=1) {
+return 1;
+}
+return n_num*fn_factorial(n_num-1);
+return 0;
+}
+int main(void){
+long long s_num;
+s_num = 3;
+printf("%lld\n", (long long)(fn_sumsquares(s_num, 4)));
+printf("%lld\n", (long long)(fn_factorial(5)));
+printf("%lld\n", (long long)(s_num));
+return 0;
+}
@@ -0,0 +1,19 @@
+#include <stdio.h>
+int main(void){
+double score_num;
+fputs("Enter a score: \n", stdout);
+if(0 == scanf("%lf", &score_num)) {
+score_num = 0;
+scanf("%*s");
+}
+if (score_num>=90) {
+fputs("A\n", stdout);
+} else if (score_num>=80) {
+fputs("B\n", stdout);
+} else if (score_num>=70) {
+fputs("C\n", stdout);
+} else {
+fputs("F\n", stdout);
+}
+return 0;
+}
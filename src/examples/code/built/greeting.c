@@ -0,0 +1,54 @@
+#include <stdio.h>
+#include <string.h>
+#define STRING_MAX 255
+typedef struct { char text[STRING_MAX+1]; } string_t;
+static string_t string_input(int skip_blanks){
+string_t s = {0};
+size_t len = 0;
+int c = getchar();
+while (c=='\n' || c=='\r' || (skip_blanks && (c==' ' || c=='\t'))) {
+c = getchar();
+}
+while (c!=EOF && c!='\n') {
+if (c!='\r' && len<STRING_MAX) {
+s.text[len++] = (char)c;
+}
+c = getchar();
+}
+return s;
+}
+static string_t string_concat(string_t a, string_t b){
+size_t len = strlen(a.text);
+size_t i = 0;
+while (b.text[i] && len<STRING_MAX) {
+a.text[len++] = b.text[i++];
+}
+a.text[len] = '\0';
+return a;
+}
+static string_t string_from(const char *text){
+string_t s = {0};
+size_t len = 0;
+while (text[len] && len<STRING_MAX) {
+s.text[len] = text[len];
+len++;
+}
+return s;
+}
+static int string_equal(string_t a, string_t b){
+return strcmp(a.text, b.text)==0;
+}
+int main(void){
+string_t name_str = {0};
+string_t greeting_str = {0};
+fputs("What is your name?\n", stdout);
+name_str = string_input(0);
+greeting_str = string_concat(string_concat(string_from("Hello, "), name_str), string_from("!"));
+printf("%s\n", greeting_str.text);
+if (string_equal(name_str, string_from("World"))) {
+fputs("That is not a name.\n", stdout);
+} else if (!string_equal(name_str, string_from(""))) {
+printf("%s\n", string_concat(string_from("Nice to meet you, "), name_str).text);
+}
+return 0;
+}
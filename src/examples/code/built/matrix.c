@@ -0,0 +1,22 @@
+#include <stdio.h>
+int main(void){
+static long long squares_arr[11] = {0};
+long long i_num;
+static long long m_arr[3][4] = {0};
+long long total_num;
+long long r_num;
+long long c_num;
+for (i_num = 0; i_num<=10; i_num = i_num+1) {
+squares_arr[(int)(i_num)] = i_num*i_num;
+}
+printf("%lld\n", (long long)(squares_arr[(int)(7)]));
+total_num = 0;
+for (r_num = 0; r_num<=2; r_num = r_num+1) {
+for (c_num = 0; c_num<=3; c_num = c_num+1) {
+m_arr[(int)(r_num)][(int)(c_num)] = r_num*10+c_num;
+total_num = total_num+m_arr[(int)(r_num)][(int)(c_num)];
+}
+}
+printf("%lld\n", (long long)(total_num));
+return 0;
+}
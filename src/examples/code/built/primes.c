@@ -0,0 +1,34 @@
+/* link with -lm */
+#include <stdio.h>
+#include <stdlib.h>
+#include <math.h>
+static long long mod_check(long long divisor){
+if (divisor==0) {
+fprintf(stderr, "Runtime error: MOD by zero\n");
+exit(1);
+}
+return divisor;
+}
+int main(void){
+long long n_num;
+long long prime_num;
+long long d_num;
+long long for_end_1;
+long long e_num;
+for (n_num = 2; n_num<=30; n_num = n_num+1) {
+prime_num = 1;
+for_end_1 = n_num-1;
+for (d_num = 2; d_num<=for_end_1; d_num = d_num+1) {
+if (n_num%mod_check(d_num)==0) {
+prime_num = 0;
+}
+}
+if (prime_num==1) {
+printf("%lld\n", (long long)(n_num));
+}
+}
+for (e_num = 0; e_num<=10; e_num = e_num+5) {
+printf("%.2f\n", (double)(fmod(pow(2, e_num), 1000)));
+}
+return 0;
+}
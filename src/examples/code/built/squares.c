@@ -0,0 +1,47 @@
+#include <stdio.h>
+int main(void){
+long long x_num;
+int gosub_stack[256];
+int gosub_top = 0;
+long long y_num;
+for (x_num = 1; x_num<=5; x_num = x_num+1) {
+if (gosub_top == 256) {
+fprintf(stderr, "Runtime error: GOSUB nested too deeply at line 4\n");
+return 1;
+}
+gosub_stack[gosub_top++] = 0;
+goto lbl_square;
+gosub_return_0:;
+}
+if (gosub_top == 256) {
+fprintf(stderr, "Runtime error: GOSUB nested too deeply at line 6\n");
+return 1;
+}
+gosub_stack[gosub_top++] = 1;
+goto lbl_done;
+gosub_return_1:;
+goto lbl_finish;
+lbl_square:
+y_num = x_num*x_num;
+printf("%lld\n", (long long)(y_num));
+if (gosub_top == 0) {
+fprintf(stderr, "Runtime error: RETURN without GOSUB at line 12\n");
+return 1;
+}
+goto gosub_dispatch;
+lbl_done:
+fputs("Done\n", stdout);
+if (gosub_top == 0) {
+fprintf(stderr, "Runtime error: RETURN without GOSUB at line 16\n");
+return 1;
+}
+goto gosub_dispatch;
+lbl_finish:
+return 0;
+gosub_dispatch:
+switch (gosub_stack[--gosub_top]) {
+case 0: goto gosub_return_0;
+case 1: goto gosub_return_1;
+}
+return 1;
+}
@@ -0,0 +1,35 @@
+/* link with -lm */
+#include <stdio.h>
+#include <math.h>
+static int print_column = 0;
+static void print_zone(void){
+do {
+putchar(' ');
+print_column++;
+} while (print_column%14);
+}
+int main(void){
+long long n_num;
+print_column += printf("%s", "n");
+print_zone();
+print_column += printf("%s", "square");
+print_zone();
+print_column += printf("%s\n", "half");
+print_column = 0;
+for (n_num = 1; n_num<=4; n_num = n_num+1) {
+print_column += printf("%lld", (long long)(n_num));
+print_zone();
+print_column += printf("%lld", (long long)(n_num*n_num));
+print_zone();
+print_column += printf("%.2f\n", (double)(n_num/(double)2));
+print_column = 0;
+}
+print_column += printf("%s", "done");
+print_column += printf("\n");
+print_column = 0;
+print_column += printf("%7.4f", (double)(sqrt(2)));
+print_zone();
+print_column += printf("%7.4f\n", (double)(1/(double)3));
+print_column = 0;
+return 0;
+}
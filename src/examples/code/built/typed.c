@@ -0,0 +1,24 @@
+#include <stdio.h>
+double fn_half(long long n_num);
+double fn_half(long long n_num){
+return n_num/(double)2;
+return 0;
+}
+int main(void){
+double count_num;
+static long long totals_arr[4] = {0};
+long long total_num;
+long long i_num;
+count_num = 0;
+total_num = 0;
+for (i_num = 1; i_num<=3; i_num = i_num+1) {
+totals_arr[(int)(i_num)] = i_num*10;
+total_num = total_num+totals_arr[(int)(i_num)];
+}
+count_num = 3;
+printf("%lld\n", (long long)(total_num));
+printf("%.2f\n", (double)(total_num/count_num));
+printf("%.2f\n", (double)(fn_half(7)));
+printf("%lld\n", (long long)(7%3));
+return 0;
+}
@@ -1,8 +1,9 @@
 use std::fmt;
+use crate::error::CompileError;
 
 const RADIX: u32 = 10;
-type RawSource = Vec<char>;
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum TokenType {
 	EOF = -1,
@@ -22,6 +23,26 @@ pub enum TokenType {
 	WHILE = 109,
 	REPEAT = 110,
 	ENDWHILE = 111,
+	ELSE = 112,
+	ELSEIF = 113,
+	FOR = 114,
+	TO = 115,
+	STEP = 116,
+	NEXT = 117,
+	GOSUB = 118,
+	RETURN = 119,
+	FUNCTION = 120,
+	ENDFUNCTION = 121,
+	DIM = 122,
+	AND = 123,
+	OR = 124,
+	NOT = 125,
+	MOD = 126,
+	RANDOMIZE = 127,
+	AS = 128,
+	INTEGER = 129,
+	FLOAT = 130,
+	USING = 131,
 	// Operators
 	EQ = 201,
 	PLUS = 202,
@@ -34,47 +55,85 @@ pub enum TokenType {
 	LTEQ = 209,
 	GT = 210,
 	GTEQ = 211,
+	LPAREN = 212,
+	RPAREN = 213,
+	COMMA = 214,
+	PERCENT = 215,
+	CARET = 216,
+	SEMICOLON = 217,
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
-pub struct Token {
-    pub text: RawSource,
-    pub kind: TokenType
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Span {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+    pub length: usize,
 }
 
-impl Token {
-    pub fn new(text: RawSource, kind: TokenType) -> Self {
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Token<'a> {
+    pub text: &'a str,
+    pub kind: TokenType,
+    pub span: Span,
+}
+
+impl<'a> Token<'a> {
+    pub fn new(text: &'a str, kind: TokenType) -> Self {
         Token {
             text,
-            kind
+            kind,
+            span: Span::default(),
         }
     }
 
-    pub fn check_keyword(text_token: &RawSource) -> Option<TokenType> {
-        match text_token[..] {
-            ['L', 'A', 'B','E', 'L'] => Some(TokenType::LABEL),
-            ['G', 'O', 'T', 'O'] => Some(TokenType::GOTO),
-            ['P', 'R', 'I', 'N', 'T'] => Some(TokenType::PRINT),
-            ['I', 'N', 'P', 'U', 'T'] => Some(TokenType::INPUT),
-            ['L', 'E', 'T'] => Some(TokenType::LET),
-            ['I', 'F'] => Some(TokenType::IF),
-            ['T', 'H', 'E', 'N'] => Some(TokenType::THEN),
-            ['E', 'N', 'D', 'I', 'F'] => Some(TokenType::ENDIF),
-            ['W', 'H', 'I', 'L', 'E'] => Some(TokenType::WHILE),
-            ['R', 'E', 'P', 'E', 'A', 'T'] => Some(TokenType::REPEAT),
-            ['E', 'N', 'D', 'W', 'H', 'I', 'L', 'E'] => Some(TokenType::ENDWHILE),
+    pub fn check_keyword(text_token: &str) -> Option<TokenType> {
+        match text_token {
+            "LABEL" => Some(TokenType::LABEL),
+            "GOTO" => Some(TokenType::GOTO),
+            "PRINT" => Some(TokenType::PRINT),
+            "INPUT" => Some(TokenType::INPUT),
+            "LET" => Some(TokenType::LET),
+            "IF" => Some(TokenType::IF),
+            "THEN" => Some(TokenType::THEN),
+            "ENDIF" => Some(TokenType::ENDIF),
+            "WHILE" => Some(TokenType::WHILE),
+            "REPEAT" => Some(TokenType::REPEAT),
+            "ENDWHILE" => Some(TokenType::ENDWHILE),
+            "ELSE" => Some(TokenType::ELSE),
+            "ELSEIF" => Some(TokenType::ELSEIF),
+            "FOR" => Some(TokenType::FOR),
+            "TO" => Some(TokenType::TO),
+            "STEP" => Some(TokenType::STEP),
+            "NEXT" => Some(TokenType::NEXT),
+            "GOSUB" => Some(TokenType::GOSUB),
+            "RETURN" => Some(TokenType::RETURN),
+            "FUNCTION" => Some(TokenType::FUNCTION),
+            "ENDFUNCTION" => Some(TokenType::ENDFUNCTION),
+            "DIM" => Some(TokenType::DIM),
+            "AND" => Some(TokenType::AND),
+            "OR" => Some(TokenType::OR),
+            "NOT" => Some(TokenType::NOT),
+            "MOD" => Some(TokenType::MOD),
+            "RANDOMIZE" => Some(TokenType::RANDOMIZE),
+            "AS" => Some(TokenType::AS),
+            "INTEGER" => Some(TokenType::INTEGER),
+            "FLOAT" => Some(TokenType::FLOAT),
+            "USING" => Some(TokenType::USING),
             _ => None
         }
     }
-
-    pub fn get_text(&self) -> String {
-        self.text.iter().collect::<String>()
-    }
 }
 
-impl fmt::Display for Token {
+impl fmt::Display for Token<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{} {}", self.kind, self.get_text())
+        write!(f, "{} {}", self.kind, self.text)
     }
 }
 
@@ -97,6 +156,26 @@ impl fmt::Display for TokenType {
             TokenType::WHILE => write!(f, "WHILE"),
             TokenType::REPEAT => write!(f, "REPEAT"),
             TokenType::ENDWHILE => write!(f, "ENDWHILE"),
+            TokenType::ELSE => write!(f, "ELSE"),
+            TokenType::ELSEIF => write!(f, "ELSEIF"),
+            TokenType::FOR => write!(f, "FOR"),
+            TokenType::TO => write!(f, "TO"),
+            TokenType::STEP => write!(f, "STEP"),
+            TokenType::NEXT => write!(f, "NEXT"),
+            TokenType::GOSUB => write!(f, "GOSUB"),
+            TokenType::RETURN => write!(f, "RETURN"),
+            TokenType::FUNCTION => write!(f, "FUNCTION"),
+            TokenType::ENDFUNCTION => write!(f, "ENDFUNCTION"),
+            TokenType::DIM => write!(f, "DIM"),
+            TokenType::AND => write!(f, "AND"),
+            TokenType::OR => write!(f, "OR"),
+            TokenType::NOT => write!(f, "NOT"),
+            TokenType::MOD => write!(f, "MOD"),
+            TokenType::RANDOMIZE => write!(f, "RANDOMIZE"),
+            TokenType::AS => write!(f, "AS"),
+            TokenType::INTEGER => write!(f, "INTEGER"),
+            TokenType::FLOAT => write!(f, "FLOAT"),
+            TokenType::USING => write!(f, "USING"),
             TokenType::EQ => write!(f, "EQ"),
             TokenType::PLUS => write!(f, "PLUS"),
             TokenType::MINUS => write!(f, "MINUS"),
@@ -108,55 +187,89 @@ impl fmt::Display for TokenType {
             TokenType::LTEQ => write!(f, "LTEQ"),
             TokenType::GT => write!(f, "GT"),
             TokenType::GTEQ => write!(f, "GTEQ"),
+            TokenType::LPAREN => write!(f, "LPAREN"),
+            TokenType::RPAREN => write!(f, "RPAREN"),
+            TokenType::COMMA => write!(f, "COMMA"),
+            TokenType::PERCENT => write!(f, "PERCENT"),
+            TokenType::CARET => write!(f, "CARET"),
+            TokenType::SEMICOLON => write!(f, "SEMICOLON"),
         }
     }
 }
 
-pub struct Lexer {
-    pub source: RawSource,
+pub struct Lexer<'a> {
+    pub source: &'a str,
     pub cur_char: char,
-    pub cur_pos: Option<usize>,
+    pub cur_pos: usize,
+    pub line: usize,
+    pub column: usize,
+    pub finished: bool,
 }
 
-impl Lexer {
+impl<'a> Lexer<'a> {
 
-    pub fn new(source: RawSource) -> Self {
-        let mut lexer = Lexer {
+    pub fn new(source: &'a str) -> Self {
+        Lexer {
            source,
-           cur_char: '\0',
-           cur_pos: None,
-        };
-
-        lexer.next_char();
-        lexer
+           cur_char: source.chars().next().unwrap_or('\0'),
+           cur_pos: 0,
+           line: 1,
+           column: 1,
+           finished: false,
+        }
     }
 
     pub fn next_char(&mut self) {
-        match self.cur_pos {
-            Some(x) =>  {
-                if x + 1 >= self.source.len() {
-                    self.cur_char = '\0';
-                } else {
-                    self.cur_char = self.source[x + 1];
-                    self.cur_pos = Some(x + 1);
-                }
-            },
-            None => {
-                self.cur_pos = Some(0);
-                self.cur_char = self.source[0];
-            }
+        if self.cur_pos >= self.source.len() {
+            return;
+        }
+
+        if self.cur_char == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
         }
+
+        self.cur_pos += self.cur_char.len_utf8();
+        self.cur_char = self.source[self.cur_pos..].chars().next().unwrap_or('\0');
     }
 
     pub fn peek(&self) -> char {
-        match self.cur_pos {
-            Some(x) => {
-                if x + 1 >= self.source.len() {
-                    return '\0'
-                }
-                return self.source[x + 1];
-            },
-            None => return '\0',
+        if self.cur_pos >= self.source.len() {
+            return '\0'
+        }
+
+        let mut chars = self.source[self.cur_pos..].chars();
+        chars.next();
+        chars.next().unwrap_or('\0')
+    }
+
+    pub fn location(&self) -> Span {
+        Span {
+            offset: self.cur_pos,
+            line: self.line,
+            column: self.column,
+            length: 0,
+        }
+    }
+
+    pub fn span_from(&self, start: Span) -> Span {
+        Span {
+            length: self.cur_pos - start.offset,
+            ..start
+        }
+    }
+
+    pub fn char_span(&self) -> Span {
+        let length = match self.cur_char {
+            '\0' => 0,
+            other => other.len_utf8(),
+        };
+
+        Span {
+            length,
+            ..self.location()
         }
     }
 
@@ -168,85 +281,108 @@ impl Lexer {
 
     pub fn skip_comment(&mut self) {
         if self.cur_char == '#' {
-            while self.cur_char != '\n' {
+            while self.cur_char != '\n' && self.cur_char != '\0' {
                 self.next_char();
             }
         }
     }
-    
-    pub fn get_token(&mut self) -> Token {
 
-        let token: Token;
+    pub fn get_token(&mut self) -> Result<Token<'a>, CompileError> {
 
         self.skip_whitespace();
         self.skip_comment();
 
-        token = match self.cur_char {
-            '+' => Token::new(vec![self.cur_char], TokenType::PLUS),
-            '-' => Token::new(vec![self.cur_char], TokenType::MINUS),
-            '*' => Token::new(vec![self.cur_char], TokenType::ASTERISK),
-            '/' => Token::new(vec![self.cur_char], TokenType::SLASH),
+        let start = self.location();
+        let kind = match self.cur_char {
+            '+' => TokenType::PLUS,
+            '-' => TokenType::MINUS,
+            '*' => TokenType::ASTERISK,
+            '/' => TokenType::SLASH,
+            '(' => TokenType::LPAREN,
+            ')' => TokenType::RPAREN,
+            ',' => TokenType::COMMA,
+            '%' => TokenType::PERCENT,
+            '^' => TokenType::CARET,
+            ';' => TokenType::SEMICOLON,
             '>' => {
                 if self.peek() == '=' {
-                    let last_char = self.cur_char;
                     self.next_char();
-
-                    Token::new(vec![last_char, self.cur_char], TokenType::GTEQ)
+                    TokenType::GTEQ
                 } else {
-                    Token::new(vec![self.cur_char], TokenType::GT)
+                    TokenType::GT
                 }
             },
             '<' => {
                 if self.peek() == '=' {
-                    let last_char = self.cur_char;
                     self.next_char();
-
-                    Token::new(vec![last_char, self.cur_char], TokenType::LTEQ)
+                    TokenType::LTEQ
                 } else {
-                    Token::new(vec![self.cur_char], TokenType::LT)
+                    TokenType::LT
                 }
             },
             '!' => {
                 if self.peek() == '=' {
-                    let last_char = self.cur_char;
                     self.next_char();
-
-                    Token::new(vec![last_char, self.cur_char], TokenType::NOTEQ)
+                    TokenType::NOTEQ
                 } else {
-                    panic!("[LEXER] ERROR: Expected !=, got !{}", self.peek())
+                    let error = CompileError::Lexical {
+                        message: format!("Expected !=, got !{}", self.peek()),
+                        span: self.char_span(),
+                    };
+                    self.next_char();
+                    return Err(error);
                 }
             },
             '=' => {
                 if self.peek() == '=' {
-                    let last_char = self.cur_char;
                     self.next_char();
-
-                    Token::new(vec![last_char, self.cur_char], TokenType::EQEQ)
+                    TokenType::EQEQ
                 } else {
-                    Token::new(vec![self.cur_char], TokenType::EQ)
+                    TokenType::EQ
                 }
             },
             '\"' => {
                 self.next_char();
-                let start_pos: usize = self.cur_pos.unwrap() as usize;
+                let mut illegal = None;
 
                 while self.cur_char != '\"' {
-                    if self.cur_char == '\r' 
-                        || self.cur_char == '\n' 
-                        || self.cur_char == '\t' 
-                        || self.cur_char == '\\' 
-                        || self.cur_char == '%' {
-                        panic!("[LEXER] ERROR: Illegal character in string.")
+                    // Stop before the line break so the NEWLINE token survives.
+                    if self.cur_char == '\0' || self.cur_char == '\r' || self.cur_char == '\n' {
+                        return Err(CompileError::Lexical {
+                            message: String::from("Unterminated string."),
+                            span: self.span_from(start),
+                        });
                     }
+                    if self.cur_char == '\\' {
+                        let escape = self.location();
+                        self.next_char();
+
+                        if !matches!(self.cur_char, 'n' | 't' | '\"' | '\\') {
+                            if self.cur_char == '\0' || self.cur_char == '\r' || self.cur_char == '\n' {
+                                continue;
+                            }
+                            if illegal.is_none() {
+                                illegal = Some((self.cur_char, Span {
+                                    length: 1 + self.cur_char.len_utf8(),
+                                    ..escape
+                                }));
+                            }
+                        }
+                    }
+                    self.next_char();
+                }
+
+                if let Some((escape, span)) = illegal {
                     self.next_char();
+                    return Err(CompileError::Lexical {
+                        message: format!("Unknown escape sequence \\{escape} in string."),
+                        span,
+                    });
                 }
 
-                let token_text = self.source[start_pos..self.cur_pos.unwrap() as usize].to_vec();
-                Token::new(token_text, TokenType::STRING)
+                TokenType::STRING
             },
             '0'..='9' => {
-                let start_pos: usize = self.cur_pos.unwrap();
-
                 while self.peek().is_digit(RADIX) {
                     self.next_char();
                 }
@@ -255,7 +391,11 @@ impl Lexer {
                     self.next_char();
 
                     if !self.peek().is_digit(RADIX) {
-                        panic!("[LEXER] ERROR: Illegal character in decimal.");
+                        self.next_char();
+                        return Err(CompileError::Lexical {
+                            message: String::from("Illegal character in decimal."),
+                            span: self.char_span(),
+                        });
                     }
 
                     while self.peek().is_digit(RADIX) {
@@ -263,28 +403,83 @@ impl Lexer {
                     }
                 }
 
-                let token_text = self.source[start_pos..=self.cur_pos.unwrap() as usize].to_vec();
-                Token::new(token_text, TokenType::NUMBER)
+                TokenType::NUMBER
             },
             'A'..='Z' | 'a'..='z' => {
-                let start_pos: usize = self.cur_pos.unwrap() as usize;
-
                 while self.peek().is_alphanumeric() {
                     self.next_char();
                 }
 
-                let token_text = self.source[start_pos..=self.cur_pos.unwrap() as usize].to_vec();
-                match Token::check_keyword(&token_text) {
-                    Some(keyword) => Token::new(token_text, keyword),
-                    None => Token::new(token_text, TokenType::IDENT)
+                // A trailing `$` marks a string variable.
+                if self.peek() == '$' {
+                    self.next_char();
                 }
+
+                let end = self.cur_pos + self.cur_char.len_utf8();
+                Token::check_keyword(&self.source[start.offset..end]).unwrap_or(TokenType::IDENT)
             },
-            '\n' => Token::new(vec![self.cur_char], TokenType::NEWLINE),
-            '\0' => Token::new(vec![], TokenType::EOF),
-            other => panic!("[LEXER] ERROR: Unknown token {other}")
+            '\n' => TokenType::NEWLINE,
+            '\0' => TokenType::EOF,
+            other => {
+                let error = CompileError::Lexical {
+                    message: format!("Unknown token {other}"),
+                    span: self.char_span(),
+                };
+                self.next_char();
+                return Err(error);
+            }
         };
 
         self.next_char();
-        token
+
+        let span = self.span_from(start);
+        let text = match kind {
+            // Strip the surrounding quotes.
+            TokenType::STRING => &self.source[span.offset + 1..self.cur_pos - 1],
+            _ => &self.source[span.offset..self.cur_pos],
+        };
+
+        Ok(Token { text, kind, span })
+    }
+}
+
+/// Replaces the escape sequences of a STRING token with the characters they
+/// stand for. The lexer has already rejected unknown escapes.
+pub fn unescape(text: &str) -> String {
+    let mut value = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => value.push('\n'),
+            Some('t') => value.push('\t'),
+            Some(other) => value.push(other),
+            None => (),
+        }
+    }
+    value
+}
+
+/// Yields every token up to and including the EOF token, then stops.
+/// Lexical errors are yielded in place and scanning resumes after them.
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token<'a>, CompileError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let token = self.get_token();
+        if matches!(&token, Ok(token) if token.kind == TokenType::EOF) {
+            self.finished = true;
+        }
+
+        Some(token)
     }
 }
@@ -0,0 +1,83 @@
+pub mod ast;
+pub mod codegen;
+pub mod diagnostics;
+pub mod emitter;
+pub mod error;
+pub mod lex;
+pub mod parse;
+pub mod types;
+#[path = "utils/utils.rs"] pub mod utils;
+
+use std::io::Write;
+use diagnostics::Diagnostics;
+use error::CompileError;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CompileOptions {
+    /// Stop after this many errors, or never with 0.
+    pub max_errors: usize,
+    /// Check array indices at run time and exit with the source location
+    /// when one is out of range.
+    pub bounds_checks: bool,
+    pub invalid_input: codegen::InvalidInput,
+}
+
+impl Default for CompileOptions {
+    fn default() -> Self {
+        CompileOptions {
+            max_errors: parse::DEFAULT_MAX_ERRORS,
+            bounds_checks: false,
+            invalid_input: codegen::InvalidInput::default(),
+        }
+    }
+}
+
+/// Splits `source` into tokens, without the trailing EOF.
+pub fn tokenize(source: &str) -> Result<Vec<lex::Token<'_>>, Diagnostics> {
+    let mut tokens = Vec::new();
+    let mut errors = Vec::new();
+
+    for token in lex::Lexer::new(source) {
+        match token {
+            Ok(token) if token.kind == lex::TokenType::EOF => (),
+            Ok(token) => tokens.push(token),
+            Err(error) => errors.push(error),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(Diagnostics::from(errors));
+    }
+
+    Ok(tokens)
+}
+
+pub fn parse_str(source: &str, options: &CompileOptions) -> Result<ast::Program, Diagnostics> {
+    let mut parser = parse::Parser::new(lex::Lexer::new(source));
+    parser.max_errors = options.max_errors;
+
+    parser.program().map_err(Diagnostics::from)
+}
+
+/// Transpiles a TinyBASIC program into C source code.
+pub fn compile_str(source: &str, options: &CompileOptions) -> Result<String, Diagnostics> {
+    generate(source, options).map(emitter::Emitter::into_string)
+}
+
+/// Transpiles a TinyBASIC program and writes the C source to `sink`.
+pub fn compile_to<W: Write>(source: &str, options: &CompileOptions, sink: &mut W) -> Result<(), Diagnostics> {
+    generate(source, options)?
+        .write_to(sink)
+        .map_err(|err| Diagnostics::from(CompileError::Io(err.to_string())))
+}
+
+fn generate(source: &str, options: &CompileOptions) -> Result<emitter::Emitter, Diagnostics> {
+    let program = parse_str(source, options)?;
+
+    let mut generator = codegen::CodeGenerator::new(emitter::Emitter::new());
+    generator.bounds_checks = options.bounds_checks;
+    generator.invalid_input = options.invalid_input;
+    generator.generate(&program);
+
+    Ok(generator.emitter)
+}
@@ -1,29 +1,78 @@
-mod lex;
-mod parse;
-mod emitter;
-#[path = "utils/utils.rs"] mod utils;
-
 use clap::{Parser};
+use simple_compiler::codegen::InvalidInput;
+use simple_compiler::diagnostics::Diagnostics;
+use simple_compiler::{compile_str, error, utils, CompileOptions};
+use std::io::Write;
+use std::process::ExitCode;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
     #[arg(short, long)]
     input_filename: std::path::PathBuf,
+    /// Output file, or `-` to write the C code to stdout
     #[arg(short, long, default_value = "out.c")]
     output_filename: std::path::PathBuf,
+    /// Stop after this many errors, or 0 to report them all
+    #[arg(long, default_value_t = CompileOptions::default().max_errors)]
+    max_errors: usize,
+    /// Exit with the source location when an array index is out of range
+    #[arg(long)]
+    bounds_checks: bool,
+    /// What the generated program does when INPUT does not get a number
+    #[arg(long, value_enum, default_value_t = InvalidInput::default())]
+    invalid_input: InvalidInput,
 }
 
-fn main() {
-    println!("Teeny Tiny Compiler");
+fn write_output(args: &Args, code: &str) -> Result<(), Diagnostics> {
+    let result = if args.output_filename.as_os_str() == "-" {
+        std::io::stdout().lock().write_all(code.as_bytes())
+    } else {
+        std::fs::write(&args.output_filename, code)
+    };
+
+    result.map_err(|err| {
+        let path = args.output_filename.display();
+        error::CompileError::Io(format!("{err} | {path}")).into()
+    })
+}
 
+fn main() -> ExitCode {
     let args = Args::parse();
+    let to_stdout = args.output_filename.as_os_str() == "-";
+
+    if !to_stdout {
+        println!("Teeny Tiny Compiler");
+    }
+
+    let filename = args.input_filename.display().to_string();
+    let options = CompileOptions {
+        max_errors: args.max_errors,
+        bounds_checks: args.bounds_checks,
+        invalid_input: args.invalid_input,
+    };
+
+    let source = match utils::read_file(&args.input_filename) {
+        Ok(source) => source,
+        Err(err) => {
+            eprint!("{}", Diagnostics::from(err).render(&filename, ""));
+            return ExitCode::FAILURE;
+        },
+    };
 
-    let mut parser = parse::Parser::new(
-        lex::Lexer::new(utils::read_file(&args.input_filename)),
-        emitter::Emitter::new(args.output_filename),
-    );
+    let result = compile_str(&source, &options)
+        .and_then(|code| write_output(&args, &code));
 
-    parser.emit();
-    println!("Compiling Completed!");
+    match result {
+        Ok(()) => {
+            if !to_stdout {
+                println!("Compiling Completed!");
+            }
+            ExitCode::SUCCESS
+        },
+        Err(diagnostics) => {
+            eprint!("{}", diagnostics.render(&filename, &source));
+            ExitCode::FAILURE
+        },
+    }
 }
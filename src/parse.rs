@@ -1,278 +1,1154 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::iter::Peekable;
+use crate::ast;
+use crate::error::CompileError;
 use crate::lex;
-use crate::emitter;
-
-pub struct Parser {
-    pub lexer: lex::Lexer,
-    pub emitter: emitter::Emitter,
-    pub cur_token: lex::Token,
-    pub peek_token: lex::Token,
-    pub symbols: HashSet<String>,
-    pub labels_declared: HashSet<String>,
-    pub labels_gotoed: HashSet<String>,
+
+pub const DEFAULT_MAX_ERRORS: usize = 20;
+pub const MAX_FORMAT_WIDTH: usize = 32;
+/// Array sizes and indices are C `int`s, so a dimension of `bound + 1`
+/// elements has to fit in one.
+pub const MAX_ARRAY_BOUND: usize = i32::MAX as usize - 1;
+
+/// Variables and labels visible to the code being parsed. The main program
+/// and every FUNCTION get a scope of their own.
+#[derive(Default)]
+pub struct Scope<'a> {
+    pub function: Option<&'a str>,
+    pub symbols: HashSet<&'a str>,
+    pub arrays: HashMap<&'a str, Array>,
+    pub labels_declared: HashMap<&'a str, lex::Span>,
+    pub labels_gotoed: HashMap<&'a str, lex::Span>,
+    pub labels_gosubed: HashMap<&'a str, lex::Span>,
+}
+
+pub struct Array {
+    pub bounds: Vec<usize>,
+    pub span: lex::Span,
 }
 
-impl Parser {
-    
-    pub fn new(mut lexer: lex::Lexer, emitter: emitter::Emitter) -> Self {
+pub struct Signature {
+    pub arity: usize,
+    pub span: lex::Span,
+}
 
-        let cur_token = lexer.get_token();
-        let peek_token = lexer.get_token();
-        let symbols = HashSet::new();
-        let labels_declared = HashSet::new();
-        let labels_gotoed = HashSet::new();
+pub struct Header<'a> {
+    pub name: &'a str,
+    pub params: Vec<&'a str>,
+    pub param_types: Vec<Option<ast::NumericType>>,
+    pub return_type: Option<ast::NumericType>,
+}
 
-        Parser{
-            lexer,
-            emitter,
-            cur_token,
-            peek_token,
-            symbols,
-            labels_declared,
-            labels_gotoed,
-        }
+pub struct Call<'a> {
+    pub name: &'a str,
+    pub arity: usize,
+    pub span: lex::Span,
+}
+
+pub struct Parser<'a> {
+    pub tokens: Peekable<lex::Lexer<'a>>,
+    pub cur_token: lex::Token<'a>,
+    pub scope: Scope<'a>,
+    pub functions: HashMap<&'a str, Signature>,
+    pub calls: Vec<Call<'a>>,
+    pub errors: Vec<CompileError>,
+    pub max_errors: usize,
+    pub terminators: Vec<lex::TokenType>,
+    pub paren_depth: usize,
+}
+
+impl<'a> Parser<'a> {
+
+    pub fn new(lexer: lex::Lexer<'a>) -> Self {
+
+        let mut parser = Parser{
+            tokens: lexer.peekable(),
+            cur_token: lex::Token::new("", lex::TokenType::EOF),
+            scope: Scope::default(),
+            functions: HashMap::new(),
+            calls: Vec::new(),
+            errors: Vec::new(),
+            max_errors: DEFAULT_MAX_ERRORS,
+            terminators: Vec::new(),
+            paren_depth: 0,
+        };
+
+        parser.next_token();
+        parser
     }
 
     pub fn check_token(&self, kind: lex::TokenType) -> bool {
         self.cur_token.kind == kind
     }
 
-    pub fn check_comparison_operator(&self) -> bool {
+    pub fn comparison_operator(&self) -> Option<ast::BinaryOp> {
         match self.cur_token.kind {
-            lex::TokenType::EQ | lex::TokenType::EQEQ 
-                | lex::TokenType::LT | lex::TokenType::LTEQ 
-                | lex::TokenType::GT | lex::TokenType::GTEQ 
-                | lex::TokenType::NOTEQ => true,
-            _ => false,
+            lex::TokenType::EQ | lex::TokenType::EQEQ => Some(ast::BinaryOp::Eq),
+            lex::TokenType::NOTEQ => Some(ast::BinaryOp::NotEq),
+            lex::TokenType::LT => Some(ast::BinaryOp::Lt),
+            lex::TokenType::LTEQ => Some(ast::BinaryOp::LtEq),
+            lex::TokenType::GT => Some(ast::BinaryOp::Gt),
+            lex::TokenType::GTEQ => Some(ast::BinaryOp::GtEq),
+            _ => None,
         }
     }
 
-    #[allow(dead_code)]
-    pub fn emit(&mut self) {
-        self.program();
-        self.emitter.write_file();
+    pub fn syntax_error(&self, message: String) -> CompileError {
+        CompileError::Syntax { message, span: self.cur_token.span }
     }
 
-    pub fn match_token(&mut self, kind: lex::TokenType) {
+    pub fn semantic_error(&self, message: String, help: Option<String>) -> CompileError {
+        CompileError::Semantic {
+            message,
+            span: self.cur_token.span,
+            notes: Vec::new(),
+            help,
+        }
+    }
+
+    pub fn match_token(&mut self, kind: lex::TokenType) -> Result<(), CompileError> {
         if !self.check_token(kind) {
 
             let current = self.cur_token.kind.to_string();
-            let expected = kind.clone().to_string();
+            let expected = kind.to_string();
 
-            panic!("[PARSER] ERROR: Expecting token of type {expected}, got {current}")
+            return Err(self.syntax_error(format!("Expecting token of type {expected}, got {current}")));
         }
 
-        self.next_token()
+        self.next_token();
+        Ok(())
+    }
+
+    pub fn match_ident(&mut self) -> Result<lex::Token<'a>, CompileError> {
+        let token = self.cur_token;
+        self.match_token(lex::TokenType::IDENT)?;
+        Ok(token)
     }
 
     pub fn next_token(&mut self) {
-        self.cur_token = self.peek_token.clone();
-        self.peek_token = self.lexer.get_token();
+        // Lexical errors are recorded and skipped so parsing can go on.
+        // Once the lexer is exhausted the EOF token stays current.
+        while let Some(token) = self.tokens.next() {
+            match token {
+                Ok(token) => {
+                    self.cur_token = token;
+                    return;
+                },
+                Err(error) => self.report(error),
+            }
+        }
     }
 
-    pub fn program(&mut self) {
-        self.emitter.header_line("#include <stdio.h>");
-        self.emitter.header_line("int main(void){");
+    pub fn report(&mut self, error: CompileError) {
+        if !self.error_limit_reached() {
+            self.errors.push(error);
+        }
+    }
+
+    /// A `max_errors` of 0 means there is no limit.
+    pub fn error_limit_reached(&self) -> bool {
+        self.max_errors != 0 && self.errors.len() >= self.max_errors
+    }
+
+    /// Skips the rest of a broken statement, stopping after its NEWLINE or
+    /// before the terminator of an enclosing block.
+    pub fn synchronize(&mut self) {
+        while !self.check_token(lex::TokenType::EOF) {
+            if self.check_token(lex::TokenType::NEWLINE) {
+                while self.check_token(lex::TokenType::NEWLINE) {
+                    self.next_token();
+                }
+                return;
+            }
+
+            if self.terminators.contains(&self.cur_token.kind) {
+                return;
+            }
+
+            self.next_token();
+        }
+    }
+
+    pub fn program(&mut self) -> Result<ast::Program, Vec<CompileError>> {
+        let mut statements = Vec::new();
+        let mut functions = Vec::new();
 
         while self.check_token(lex::TokenType::NEWLINE) {
             self.next_token();
         }
 
-        while !self.check_token(lex::TokenType::EOF) {
-            self.statement();
+        while !self.check_token(lex::TokenType::EOF) && !self.error_limit_reached() {
+            if self.check_token(lex::TokenType::FUNCTION) {
+                if let Some(function) = self.function() {
+                    functions.push(function);
+                }
+            } else if let Some(statement) = self.recover_statement() {
+                statements.push(statement);
+            }
+        }
+
+        self.check_labels();
+        self.check_calls();
+
+        if !self.errors.is_empty() {
+            let mut errors = std::mem::take(&mut self.errors);
+            errors.sort_by_key(|error| error.span().map(|span| span.offset));
+            return Err(errors);
+        }
+
+        Ok(ast::Program { statements, functions })
+    }
+
+    /// Reports GOTO and GOSUB targets that the current scope never declares.
+    pub fn check_labels(&mut self) {
+        let scope = &self.scope;
+        let gotos = scope.labels_gotoed.iter().map(|(label, span)| ("GOTO", *label, *span));
+        let gosubs = scope.labels_gosubed.iter().map(|(label, span)| ("GOSUB", *label, *span));
+        let mut undeclared = gotos.chain(gosubs)
+            .filter(|(_, label, _)| !scope.labels_declared.contains_key(*label))
+            .collect::<Vec<_>>();
+        undeclared.sort_by_key(|(_, _, span)| span.offset);
+
+        for (keyword, label, span) in undeclared {
+            self.report(CompileError::Semantic {
+                message: format!("{keyword} to undeclared label {label}"),
+                span,
+                notes: Vec::new(),
+                help: Some(format!("declare it somewhere in the program with `LABEL {label}`")),
+            });
+        }
+    }
+
+    /// Calls may come before the FUNCTION they refer to, so they are only
+    /// checked once the whole program is parsed.
+    pub fn check_calls(&mut self) {
+        for call in std::mem::take(&mut self.calls) {
+            let error = match self.functions.get(call.name) {
+                None => CompileError::Semantic {
+                    message: format!("Call to undeclared function {}", call.name),
+                    span: call.span,
+                    notes: Vec::new(),
+                    help: Some(format!("define it with `FUNCTION {}(...)`", call.name)),
+                },
+                Some(signature) if signature.arity != call.arity => CompileError::Semantic {
+                    message: format!("Function {} expects {}, got {}", call.name, plural(signature.arity, "argument", "arguments"), call.arity),
+                    span: call.span,
+                    notes: vec![format!("{} is defined at {}", call.name, signature.span)],
+                    help: None,
+                },
+                Some(_) => continue,
+            };
+            self.report(error);
         }
+    }
+
+    /// Parses a FUNCTION definition in a scope of its own. A broken header
+    /// skips the whole definition.
+    pub fn function(&mut self) -> Option<ast::Function> {
+        self.next_token();
 
-        self.emitter.emit_line("return 0;");
-        self.emitter.emit_line("}");
+        let header = match self.function_header() {
+            Ok(header) => header,
+            Err(error) => {
+                self.report(error);
+                while !self.check_token(lex::TokenType::EOF) && !self.check_token(lex::TokenType::ENDFUNCTION) {
+                    self.next_token();
+                }
+                self.next_token();
+                self.synchronize();
+                return None;
+            },
+        };
+
+        let scope = Scope {
+            function: Some(header.name),
+            symbols: header.params.iter().copied().collect(),
+            ..Scope::default()
+        };
+        let outer = std::mem::replace(&mut self.scope, scope);
+        let body = self.block(&[lex::TokenType::ENDFUNCTION]);
+        self.check_labels();
+        self.scope = outer;
 
-        for label in self.labels_gotoed.iter() {
-            if !self.labels_declared.contains(label) {
-                panic!("[PARSER] Error: GOTO to undeclared label {label}")
+        let body = body.and_then(|body| {
+            self.match_token(lex::TokenType::ENDFUNCTION)?;
+            self.nl()?;
+            Ok(body)
+        });
+
+        match body {
+            Ok(body) => Some(ast::Function {
+                name: header.name.to_string(),
+                params: header.params.iter().map(|param| param.to_string()).collect(),
+                param_types: header.param_types,
+                return_type: header.return_type,
+                body,
+            }),
+            Err(error) => {
+                self.report(error);
+                self.synchronize();
+                None
+            },
+        }
+    }
+
+    pub fn function_header(&mut self) -> Result<Header<'a>, CompileError> {
+        let name = self.match_ident()?;
+        self.check_number_name(name, "A FUNCTION");
+        self.check_not_intrinsic(name);
+        self.match_token(lex::TokenType::LPAREN)?;
+
+        let mut params = Vec::new();
+        let mut param_types = Vec::new();
+        if !self.check_token(lex::TokenType::RPAREN) {
+            loop {
+                let param = self.match_ident()?;
+                self.check_number_name(param, "A FUNCTION parameter");
+                let numeric_type = self.annotation()?;
+                if params.contains(&param.text) {
+                    let error = CompileError::Semantic {
+                        message: format!("Parameter {} is declared twice in {}", param.text, name.text),
+                        span: param.span,
+                        notes: Vec::new(),
+                        help: Some(String::from("rename one of the parameters")),
+                    };
+                    self.report(error);
+                } else {
+                    params.push(param.text);
+                    param_types.push(numeric_type);
+                }
+
+                if !self.check_token(lex::TokenType::COMMA) {
+                    break;
+                }
+                self.next_token();
             }
         }
+        self.match_token(lex::TokenType::RPAREN)?;
+        let return_type = self.annotation()?;
+        self.nl()?;
+
+        if let Some(first) = self.functions.get(name.text) {
+            let error = CompileError::Semantic {
+                message: format!("Function {} already exists", name.text),
+                span: name.span,
+                notes: vec![format!("{} was first defined at {}", name.text, first.span)],
+                help: Some(String::from("rename one of the functions")),
+            };
+            self.report(error);
+        } else {
+            self.functions.insert(name.text, Signature { arity: params.len(), span: name.span });
+        }
+
+        Ok(Header { name: name.text, params, param_types, return_type })
     }
 
-    pub fn statement(&mut self) {
+    /// Parses an optional `AS INTEGER` or `AS FLOAT`.
+    pub fn annotation(&mut self) -> Result<Option<ast::NumericType>, CompileError> {
+        if !self.check_token(lex::TokenType::AS) {
+            return Ok(None);
+        }
 
-        if self.check_token(lex::TokenType::PRINT) {
-            self.next_token();
+        self.next_token();
+        self.numeric_type().map(Some)
+    }
 
-            if self.check_token(lex::TokenType::STRING) {
-                
-                self.emitter.emit("printf(\"");
-                self.emitter.emit(self.cur_token.get_text().as_str());
-                self.emitter.emit_line("\\n\");");
+    pub fn numeric_type(&mut self) -> Result<ast::NumericType, CompileError> {
+        let numeric_type = match self.cur_token.kind {
+            lex::TokenType::INTEGER => ast::NumericType::Integer,
+            lex::TokenType::FLOAT => ast::NumericType::Float,
+            _ => {
+                let current = self.cur_token.kind.to_string();
+                return Err(self.syntax_error(format!("Expected INTEGER or FLOAT, got {current}")));
+            },
+        };
+
+        self.next_token();
+        Ok(numeric_type)
+    }
 
+    pub fn recover_statement(&mut self) -> Option<ast::Statement> {
+        match self.statement() {
+            Ok(statement) => Some(statement),
+            Err(error) => {
+                self.report(error);
+                self.synchronize();
+                None
+            },
+        }
+    }
+
+    pub fn statement(&mut self) -> Result<ast::Statement, CompileError> {
+
+        let statement = if self.check_token(lex::TokenType::PRINT) {
+            self.next_token();
+
+            if self.check_token(lex::TokenType::USING) {
                 self.next_token();
+                let format = self.print_format()?;
+                self.match_token(lex::TokenType::SEMICOLON)?;
+
+                if self.check_token(lex::TokenType::NEWLINE) {
+                    return Err(self.syntax_error(String::from("PRINT USING needs a value to format")));
+                }
+                self.print_list(Some(format))?
             } else {
-                self.emitter.emit("printf(\"%.2f\\n\", (float)(");
-                self.expression();
-                self.emitter.emit_line("));");
+                self.print_list(None)?
             }
 
         } else if self.check_token(lex::TokenType::IF) {
             self.next_token();
-            self.emitter.emit("if (");
-            self.comparison();
+            let branch_end = [lex::TokenType::ELSEIF, lex::TokenType::ELSE, lex::TokenType::ENDIF];
+            let condition = self.block_header(lex::TokenType::THEN);
+            let body = self.block(&branch_end)?;
 
-            self.match_token(lex::TokenType::THEN); 
-            self.nl();
-            self.emitter.emit_line(") {");
+            let mut else_ifs = Vec::new();
+            while self.check_token(lex::TokenType::ELSEIF) {
+                self.next_token();
+                let condition = self.block_header(lex::TokenType::THEN);
+                let body = self.block(&branch_end)?;
 
-            while !self.check_token(lex::TokenType::ENDIF) {
-                self.statement()
+                else_ifs.push(ast::ElseIf { condition, body });
             }
 
-            self.match_token(lex::TokenType::ENDIF);
-            self.emitter.emit_line("}");
+            let else_body = if self.check_token(lex::TokenType::ELSE) {
+                self.next_token();
+                self.nl()?;
+                Some(self.block(&[lex::TokenType::ENDIF])?)
+            } else {
+                None
+            };
+            self.match_token(lex::TokenType::ENDIF)?;
+
+            ast::Statement::If { condition, body, else_ifs, else_body }
         } else if self.check_token(lex::TokenType::WHILE) {
             self.next_token();
-            self.emitter.emit("while (");
-            self.comparison();
+            let condition = self.block_header(lex::TokenType::REPEAT);
+            let body = self.block(&[lex::TokenType::ENDWHILE])?;
+            self.match_token(lex::TokenType::ENDWHILE)?;
 
-            self.match_token(lex::TokenType::REPEAT);
-            self.nl();
-            self.emitter.emit_line(") {");
+            ast::Statement::While { condition, body }
+        } else if self.check_token(lex::TokenType::FOR) {
+            self.next_token();
+
+            let variable = self.match_ident()?;
+            self.check_number_name(variable, "A FOR variable");
+            self.check_scalar(variable);
+            self.scope.symbols.insert(variable.text);
+            self.match_token(lex::TokenType::EQ)?;
+            let start = self.typed_expression(false)?;
+            self.match_token(lex::TokenType::TO)?;
+            let end = self.typed_expression(false)?;
 
-            while !self.check_token(lex::TokenType::ENDWHILE) {
-                self.statement();
+            let step = if self.check_token(lex::TokenType::STEP) {
+                self.next_token();
+                Some(self.typed_expression(false)?)
+            } else {
+                None
+            };
+            self.nl()?;
+
+            let body = self.block(&[lex::TokenType::NEXT])?;
+            self.match_token(lex::TokenType::NEXT)?;
+
+            if self.check_token(lex::TokenType::IDENT) {
+                if self.cur_token.text != variable.text {
+                    let error = CompileError::Semantic {
+                        message: format!("NEXT {} does not match FOR {}", self.cur_token.text, variable.text),
+                        span: self.cur_token.span,
+                        notes: vec![format!("the loop over {} starts at {}", variable.text, variable.span)],
+                        help: Some(format!("use `NEXT {}` or a plain `NEXT`", variable.text)),
+                    };
+                    self.report(error);
+                }
+                self.next_token();
             }
 
-            self.match_token(lex::TokenType::ENDWHILE);
-            self.emitter.emit_line("}");
+            ast::Statement::For {
+                variable: variable.text.to_string(),
+                start,
+                end,
+                step,
+                body,
+            }
         } else if self.check_token(lex::TokenType::LABEL) {
             self.next_token();
 
-            let token_text = self.cur_token.get_text();
-            if self.labels_declared.contains(&token_text) {
-                panic!("[PARSER] Error: Label {token_text} already exists");
+            let token = self.match_ident()?;
+            let token_text = token.text;
+            if let Some(first) = self.scope.labels_declared.get(token_text) {
+                let error = CompileError::Semantic {
+                    message: format!("Label {token_text} already exists"),
+                    span: token.span,
+                    notes: vec![format!("{token_text} was first declared at {first}")],
+                    help: Some(String::from("rename one of the labels")),
+                };
+                self.report(error);
+            } else {
+                self.scope.labels_declared.insert(token_text, token.span);
             }
 
-            self.labels_declared.insert(token_text);
-            self.emitter.emit(self.cur_token.get_text().as_str());
-            self.emitter.emit_line(":");
-            self.match_token(lex::TokenType::IDENT);
+            ast::Statement::Label(token_text.to_string())
         } else if self.check_token(lex::TokenType::GOTO) {
             self.next_token();
-            self.labels_gotoed.insert(self.cur_token.get_text());
-            self.emitter.emit("goto");
-            self.emitter.emit(self.cur_token.get_text().as_str());
-            self.emitter.emit_line(";");
-            self.match_token(lex::TokenType::GOTO);
+
+            let token = self.match_ident()?;
+            self.scope.labels_gotoed.entry(token.text).or_insert(token.span);
+
+            ast::Statement::Goto(token.text.to_string())
+        } else if self.check_token(lex::TokenType::GOSUB) {
+            let line = self.cur_token.span.line;
+            if let Some(function) = self.scope.function {
+                let error = self.semantic_error(
+                    format!("GOSUB cannot be used inside FUNCTION {function}"),
+                    Some(String::from("call another FUNCTION instead")),
+                );
+                self.report(error);
+            }
+            self.next_token();
+
+            let token = self.match_ident()?;
+            self.scope.labels_gosubed.entry(token.text).or_insert(token.span);
+
+            ast::Statement::Gosub { label: token.text.to_string(), line }
+        } else if self.check_token(lex::TokenType::RETURN) {
+            let line = self.cur_token.span.line;
+            self.next_token();
+
+            // Inside a FUNCTION, RETURN hands back a value instead of ending a GOSUB.
+            let value = match self.scope.function {
+                Some(_) => Some(self.typed_expression(false)?),
+                None => None,
+            };
+
+            ast::Statement::Return { line, value }
         } else if self.check_token(lex::TokenType::LET) {
             self.next_token();
 
-            let token_text = self.cur_token.get_text();
-            if !self.symbols.contains(&token_text) {
-                self.symbols.insert(token_text);
-                self.emitter.header("float ");
-                self.emitter.header(self.cur_token.get_text().as_str());
-                self.emitter.header_line(";");
+            let name = self.match_ident()?;
+            if self.check_token(lex::TokenType::LPAREN) {
+                let element = self.element(name)?;
+                self.match_token(lex::TokenType::EQ)?;
+                let value = self.typed_expression(false)?;
+
+                ast::Statement::SetElement { element, value }
+            } else {
+                self.check_scalar(name);
+                self.scope.symbols.insert(name.text);
+                self.match_token(lex::TokenType::EQ)?;
+                let value = self.typed_expression(name.text.ends_with('$'))?;
+
+                ast::Statement::Let { name: name.text.to_string(), value }
             }
+        } else if self.check_token(lex::TokenType::DIM) {
+            self.next_token();
+
+            let name = self.match_ident()?;
+            self.check_not_intrinsic(name);
 
-            self.emitter.emit(self.cur_token.get_text().as_str());
-            self.emitter.emit(" = ");
-            self.match_token(lex::TokenType::IDENT);
-            self.match_token(lex::TokenType::EQ);
-            self.expression();
-            self.emitter.emit_line(";");
+            if self.check_token(lex::TokenType::LPAREN) {
+                self.array_declaration(name)?
+            } else {
+                self.typed_declaration(name)?
+            }
         } else if self.check_token(lex::TokenType::INPUT) {
             self.next_token();
 
-            let token_text = self.cur_token.get_text();
-            if !self.symbols.contains(&token_text) {
-                self.symbols.insert(token_text);
-                self.emitter.header("float ");
-                self.emitter.header(self.cur_token.get_text().as_str());
-                self.emitter.header_line(";");
+            let mut prompt = None;
+            if self.check_token(lex::TokenType::STRING) {
+                prompt = Some(lex::unescape(self.cur_token.text));
+                self.next_token();
+                self.match_token(lex::TokenType::SEMICOLON)?;
+            }
+
+            let mut names = Vec::new();
+            loop {
+                let name = self.match_ident()?;
+                self.check_scalar(name);
+                self.scope.symbols.insert(name.text);
+                names.push(name.text.to_string());
+
+                if !self.check_token(lex::TokenType::COMMA) {
+                    break;
+                }
+                self.next_token();
+            }
+
+            ast::Statement::Input { prompt, names }
+        } else if self.check_token(lex::TokenType::RANDOMIZE) {
+            self.next_token();
+
+            ast::Statement::Randomize(self.typed_expression(false)?)
+        } else if self.check_token(lex::TokenType::FUNCTION) {
+            return Err(self.syntax_error(String::from("FUNCTION can only be defined at the top level")));
+        } else {
+            let current = self.cur_token.kind.to_string();
+            return Err(self.syntax_error(format!("Statement cannot start with {current}")));
+        };
+
+        self.nl()?;
+        Ok(statement)
+    }
+
+    /// Items are separated by `;`, which prints nothing between them, or `,`,
+    /// which pads to the next tab zone. A separator at the end of the line
+    /// keeps the cursor on it.
+    pub fn print_list(&mut self, format: Option<ast::Format>) -> Result<ast::Statement, CompileError> {
+        let mut items = Vec::new();
+
+        while !self.check_token(lex::TokenType::NEWLINE) && !self.check_token(lex::TokenType::EOF) {
+            let start = self.cur_token;
+            let expression = self.expression()?;
+
+            if format.is_some() && expression.is_text() {
+                self.report(CompileError::Semantic {
+                    message: String::from("PRINT USING can only format numbers"),
+                    span: start.span,
+                    notes: Vec::new(),
+                    help: None,
+                });
+            }
+
+            items.push(match expression {
+                ast::Expression::Text(text) => ast::Printable::Text(text),
+                expression => ast::Printable::Expr(expression),
+            });
+
+            if self.check_token(lex::TokenType::COMMA) {
+                items.push(ast::Printable::Zone);
+            } else if !self.check_token(lex::TokenType::SEMICOLON) {
+                return Ok(ast::Statement::Print { items, newline: true, format });
+            }
+            self.next_token();
+        }
+
+        // Only reached on an empty PRINT or after a trailing separator.
+        let newline = items.is_empty();
+        Ok(ast::Statement::Print { items, newline, format })
+    }
+
+    /// Checks a PRINT USING format here so the generated `printf` only ever
+    /// sees a specifier built from its parts.
+    pub fn print_format(&mut self) -> Result<ast::Format, CompileError> {
+        if !self.check_token(lex::TokenType::STRING) {
+            let current = self.cur_token.kind.to_string();
+            return Err(self.syntax_error(format!("Expected a format string, got {current}")));
+        }
+
+        let text = self.cur_token.text;
+        let positions = text.strip_prefix('+').unwrap_or(text);
+        let (digits, decimals) = match positions.split_once('.') {
+            Some((digits, decimals)) => (digits, Some(decimals)),
+            None => (positions, None),
+        };
+
+        let zeros = !digits.is_empty() && digits.chars().all(|c| c == '0');
+        let valid = (zeros || (!digits.is_empty() && digits.chars().all(|c| c == '#')))
+            && decimals.is_none_or(|decimals| !decimals.is_empty() && decimals.chars().all(|c| c == '#'))
+            && text.len() <= MAX_FORMAT_WIDTH;
+
+        if !valid {
+            return Err(self.semantic_error(
+                format!("Invalid PRINT USING format \"{text}\""),
+                Some(format!("use # or 0 for each digit and . before the decimals, e.g. \"###.##\", at most {MAX_FORMAT_WIDTH} characters")),
+            ));
+        }
+
+        self.next_token();
+        Ok(ast::Format {
+            width: text.len(),
+            precision: decimals.map_or(0, str::len),
+            sign: positions.len() < text.len(),
+            zeros,
+        })
+    }
+
+    /// Parses `comparison keyword nl`. A broken header is reported and
+    /// replaced so the block body is still checked.
+    pub fn block_header(&mut self, keyword: lex::TokenType) -> ast::Expression {
+        let header = self.condition().and_then(|condition| {
+            self.match_token(keyword)?;
+            self.nl()?;
+            Ok(condition)
+        });
+
+        header.unwrap_or_else(|error| {
+            self.report(error);
+            self.synchronize();
+            ast::Expression::Number(String::from("0"))
+        })
+    }
+
+    /// Parses statements up to one of `terminators`, which is left for the
+    /// caller to consume. The last terminator is the one that closes the block.
+    pub fn block(&mut self, terminators: &[lex::TokenType]) -> Result<Vec<ast::Statement>, CompileError> {
+        let mut body = Vec::new();
+        self.terminators.extend_from_slice(terminators);
+
+        while !self.check_token(lex::TokenType::EOF)
+            && !self.terminators.contains(&self.cur_token.kind)
+            && !self.error_limit_reached() {
+            if let Some(statement) = self.recover_statement() {
+                body.push(statement);
             }
+        }
+
+        self.terminators.truncate(self.terminators.len() - terminators.len());
+
+        if !terminators.contains(&self.cur_token.kind) {
+            let closing = terminators[terminators.len() - 1];
+            self.match_token(closing)?;
+        }
+        Ok(body)
+    }
+
+    /// Parses the rest of `DIM a(3, 4) [AS type]`.
+    pub fn array_declaration(&mut self, name: lex::Token<'a>) -> Result<ast::Statement, CompileError> {
+        self.check_number_name(name, "An array");
+        self.match_token(lex::TokenType::LPAREN)?;
+        let mut bounds = vec![self.array_bound()?];
+        while self.check_token(lex::TokenType::COMMA) {
+            self.next_token();
+            bounds.push(self.array_bound()?);
+        }
+        self.match_token(lex::TokenType::RPAREN)?;
+        let numeric_type = self.annotation()?;
 
-            self.emitter.emit("if(0 == scanf(\"%f\", &");
-            self.emitter.emit(self.cur_token.get_text().as_str());
-            self.emitter.emit_line(")) {");
-            self.emitter.emit(self.cur_token.get_text().as_str());
-            self.emitter.emit_line(" = 0;");
-            self.emitter.emit_line("scanf(\"%*s\");");
-            self.emitter.emit_line("}");
-            self.match_token(lex::TokenType::IDENT);
+        if let Some(first) = self.scope.arrays.get(name.text) {
+            let error = CompileError::Semantic {
+                message: format!("Array {} already exists", name.text),
+                span: name.span,
+                notes: vec![format!("{} was first declared at {}", name.text, first.span)],
+                help: Some(String::from("rename one of the arrays")),
+            };
+            self.report(error);
+        } else if self.scope.symbols.contains(name.text) {
+            let error = CompileError::Semantic {
+                message: format!("{} is already a variable", name.text),
+                span: name.span,
+                notes: Vec::new(),
+                help: Some(String::from("give the array a name of its own")),
+            };
+            self.report(error);
         } else {
-            panic!("[PARSER] Error: Token not valid!");
+            self.scope.arrays.insert(name.text, Array { bounds: bounds.clone(), span: name.span });
         }
 
-        self.nl();
+        Ok(ast::Statement::Dim { name: name.text.to_string(), bounds, numeric_type })
+    }
+
+    /// Parses the rest of `DIM x AS type`, which declares a variable that
+    /// starts out as 0.
+    pub fn typed_declaration(&mut self, name: lex::Token<'a>) -> Result<ast::Statement, CompileError> {
+        self.check_number_name(name, "A typed variable");
+        self.match_token(lex::TokenType::AS)?;
+        let numeric_type = self.numeric_type()?;
+
+        self.check_scalar(name);
+        if self.scope.symbols.contains(name.text) {
+            let error = CompileError::Semantic {
+                message: format!("{} is already a variable", name.text),
+                span: name.span,
+                notes: Vec::new(),
+                help: Some(String::from("declare its type before its first use")),
+            };
+            self.report(error);
+        }
+        self.scope.symbols.insert(name.text);
+
+        Ok(ast::Statement::Dim { name: name.text.to_string(), bounds: Vec::new(), numeric_type: Some(numeric_type) })
+    }
+
+    pub fn array_bound(&mut self) -> Result<usize, CompileError> {
+        let token = self.cur_token;
+        self.match_token(lex::TokenType::NUMBER)?;
+
+        let (message, help) = match token.text.parse::<usize>() {
+            Ok(bound) if bound <= MAX_ARRAY_BOUND => return Ok(bound),
+            _ if token.text.contains('.') => (format!("Array bound must be a whole number, got {}", token.text), None),
+            _ => (
+                format!("Array bound {} is too large", token.text),
+                Some(format!("the largest bound is {MAX_ARRAY_BOUND}")),
+            ),
+        };
+
+        Err(CompileError::Semantic {
+            message,
+            span: token.span,
+            notes: Vec::new(),
+            help,
+        })
+    }
+
+    /// Parses the indices after an array name, checking them against its DIM.
+    pub fn element(&mut self, name: lex::Token<'a>) -> Result<ast::Element, CompileError> {
+        let indices = self.expression_list()?;
+
+        let error = match self.scope.arrays.get(name.text) {
+            None => Some(CompileError::Semantic {
+                message: format!("{} is not an array", name.text),
+                span: name.span,
+                notes: Vec::new(),
+                help: Some(format!("declare it first with `DIM {}(...)`", name.text)),
+            }),
+            Some(array) if array.bounds.len() != indices.len() => Some(CompileError::Semantic {
+                message: format!("Array {} expects {}, got {}", name.text, plural(array.bounds.len(), "index", "indices"), indices.len()),
+                span: name.span,
+                notes: vec![format!("{} is declared at {}", name.text, array.span)],
+                help: None,
+            }),
+            Some(array) => indices.iter().zip(array.bounds.iter()).find_map(|(index, bound)| match index {
+                ast::Expression::Number(text) if text.parse::<f64>().is_ok_and(|value| value >= *bound as f64 + 1.0) => Some(CompileError::Semantic {
+                    message: format!("Index {text} is out of range for {}", name.text),
+                    span: name.span,
+                    notes: vec![format!("{} is declared at {} with indices 0 to {bound}", name.text, array.span)],
+                    help: None,
+                }),
+                _ => None,
+            }),
+        };
+
+        if let Some(error) = error {
+            self.report(error);
+        }
+        Ok(ast::Element { array: name.text.to_string(), indices, span: name.span })
+    }
+
+    /// Reports an array used where a plain variable is expected.
+    pub fn check_scalar(&mut self, name: lex::Token<'a>) {
+        if self.scope.arrays.contains_key(name.text) {
+            let error = CompileError::Semantic {
+                message: format!("{} is an array, not a variable", name.text),
+                span: name.span,
+                notes: Vec::new(),
+                help: Some(format!("index it, as in `{}(0)`", name.text)),
+            };
+            self.report(error);
+        }
+    }
+
+    /// Parses an expression that has to be a string when `text` is set and a
+    /// number otherwise.
+    pub fn typed_expression(&mut self, text: bool) -> Result<ast::Expression, CompileError> {
+        let span = self.cur_token.span;
+        let expression = self.expression()?;
+
+        if expression.is_text() != text {
+            let error = CompileError::Semantic {
+                message: format!("Expected a {}, found a {}", type_name(text), type_name(!text)),
+                span,
+                notes: Vec::new(),
+                help: None,
+            };
+            self.report(error);
+        }
+        Ok(expression)
+    }
+
+    pub fn check_operands(&mut self, operator: lex::Token<'a>, op: ast::BinaryOp, lhs: &ast::Expression, rhs: &ast::Expression) {
+        let message = match (lhs.is_text(), rhs.is_text()) {
+            (false, false) => return,
+            (true, true) if matches!(op, ast::BinaryOp::Add | ast::BinaryOp::Eq | ast::BinaryOp::NotEq) => return,
+            (true, true) => format!("Operator {} is not defined for strings", operator.text),
+            _ => format!("Operator {} cannot mix a string and a number", operator.text),
+        };
+
+        self.report(CompileError::Semantic {
+            message,
+            span: operator.span,
+            notes: Vec::new(),
+            help: None,
+        });
+    }
+
+    /// Reports a `$` name where only numbers are supported.
+    pub fn check_number_name(&mut self, name: lex::Token<'a>, context: &str) {
+        if name.text.ends_with('$') {
+            let error = CompileError::Semantic {
+                message: format!("{context} must be a number, but {} is a string", name.text),
+                span: name.span,
+                notes: Vec::new(),
+                help: None,
+            };
+            self.report(error);
+        }
+    }
+
+    pub fn check_not_intrinsic(&mut self, name: lex::Token<'a>) {
+        if ast::Intrinsic::from_name(name.text).is_some() {
+            let error = CompileError::Semantic {
+                message: format!("{} is a built-in function", name.text),
+                span: name.span,
+                notes: Vec::new(),
+                help: Some(String::from("choose another name")),
+            };
+            self.report(error);
+        }
     }
 
-    pub fn nl(&mut self) {
-        self.match_token(lex::TokenType::NEWLINE);
+    /// Parses `"(" [expression {"," expression}] ")"`.
+    pub fn expression_list(&mut self) -> Result<Vec<ast::Expression>, CompileError> {
+        self.match_token(lex::TokenType::LPAREN)?;
+
+        let mut expressions = Vec::new();
+        if !self.check_token(lex::TokenType::RPAREN) {
+            expressions.push(self.typed_expression(false)?);
+            while self.check_token(lex::TokenType::COMMA) {
+                self.next_token();
+                expressions.push(self.typed_expression(false)?);
+            }
+        }
+
+        self.match_token(lex::TokenType::RPAREN)?;
+        Ok(expressions)
+    }
+
+    pub fn nl(&mut self) -> Result<(), CompileError> {
+        self.match_token(lex::TokenType::NEWLINE)?;
         while self.check_token(lex::TokenType::NEWLINE) {
             self.next_token();
         }
+        Ok(())
     }
 
-    pub fn expression(&mut self) {
-        self.term();
+    pub fn expression(&mut self) -> Result<ast::Expression, CompileError> {
+        let mut lhs = self.term()?;
+
+        loop {
+            let op = match self.cur_token.kind {
+                lex::TokenType::PLUS => ast::BinaryOp::Add,
+                lex::TokenType::MINUS => ast::BinaryOp::Sub,
+                _ => break,
+            };
 
-        while self.check_token(lex::TokenType::PLUS) || self.check_token(lex::TokenType::MINUS) {
-            self.emitter.emit(self.cur_token.get_text().as_str());
+            let operator = self.cur_token;
             self.next_token();
-            self.term();
+            let rhs = self.term()?;
+            self.check_operands(operator, op, &lhs, &rhs);
+            lhs = ast::Expression::Binary { op, lhs: Box::new(lhs), rhs: Box::new(rhs) };
         }
+
+        Ok(lhs)
     }
 
-    pub fn comparison(&mut self) {
-        self.expression();
+    pub fn condition(&mut self) -> Result<ast::Expression, CompileError> {
+        let mut lhs = self.conjunction()?;
 
-        if self.check_comparison_operator() {
-            self.emitter.emit(self.cur_token.get_text().as_str());
+        while self.check_token(lex::TokenType::OR) {
+            let operator = self.cur_token;
             self.next_token();
-            self.expression();
-        } else {
+            let rhs = self.conjunction()?;
+            self.check_operands(operator, ast::BinaryOp::Or, &lhs, &rhs);
+            lhs = ast::Expression::Binary { op: ast::BinaryOp::Or, lhs: Box::new(lhs), rhs: Box::new(rhs) };
+        }
+
+        Ok(lhs)
+    }
+
+    pub fn conjunction(&mut self) -> Result<ast::Expression, CompileError> {
+        let mut lhs = self.negation()?;
+
+        while self.check_token(lex::TokenType::AND) {
+            let operator = self.cur_token;
+            self.next_token();
+            let rhs = self.negation()?;
+            self.check_operands(operator, ast::BinaryOp::And, &lhs, &rhs);
+            lhs = ast::Expression::Binary { op: ast::BinaryOp::And, lhs: Box::new(lhs), rhs: Box::new(rhs) };
+        }
+
+        Ok(lhs)
+    }
+
+    pub fn negation(&mut self) -> Result<ast::Expression, CompileError> {
+        if self.check_token(lex::TokenType::NOT) {
+            let operator = self.cur_token;
+            self.next_token();
+            let operand = self.negation()?;
+
+            if operand.is_text() {
+                self.report(CompileError::Semantic {
+                    message: String::from("Operator NOT is not defined for strings"),
+                    span: operator.span,
+                    notes: Vec::new(),
+                    help: None,
+                });
+            }
+            return Ok(ast::Expression::Unary { op: ast::UnaryOp::Not, operand: Box::new(operand) });
+        }
+
+        self.comparison()
+    }
+
+    pub fn comparison(&mut self) -> Result<ast::Expression, CompileError> {
+        let mut lhs = self.expression()?;
+
+        // Parentheses may hold either a condition or a plain expression.
+        if self.comparison_operator().is_none() && (lhs.is_condition() || self.paren_depth > 0) {
+            return Ok(lhs);
+        }
+
+        if self.comparison_operator().is_none() {
             let current = self.cur_token.kind.to_string();
-            panic!("[PARSER] Error: Expected comparison operator at: {current}");
+            return Err(self.syntax_error(format!("Expected comparison operator at: {current}")));
         }
 
-        while self.check_comparison_operator() {
-            self.emitter.emit(self.cur_token.get_text().as_str());
+        while let Some(op) = self.comparison_operator() {
+            let operator = self.cur_token;
             self.next_token();
-            self.expression();
+            let rhs = self.expression()?;
+            self.check_operands(operator, op, &lhs, &rhs);
+            lhs = ast::Expression::Binary { op, lhs: Box::new(lhs), rhs: Box::new(rhs) };
         }
+
+        Ok(lhs)
     }
 
-    pub fn term(&mut self) {
-        self.unary();
+    pub fn term(&mut self) -> Result<ast::Expression, CompileError> {
+        let mut lhs = self.unary()?;
+
+        loop {
+            let op = match self.cur_token.kind {
+                lex::TokenType::ASTERISK => ast::BinaryOp::Mul,
+                lex::TokenType::SLASH => ast::BinaryOp::Div,
+                lex::TokenType::MOD | lex::TokenType::PERCENT => ast::BinaryOp::Mod,
+                _ => break,
+            };
 
-        while self.check_token(lex::TokenType::ASTERISK) || self.check_token(lex::TokenType::SLASH) {
-            self.emitter.emit(self.cur_token.get_text().as_str());
+            let operator = self.cur_token;
             self.next_token();
-            self.unary();
+            let rhs = self.unary()?;
+            self.check_operands(operator, op, &lhs, &rhs);
+            lhs = ast::Expression::Binary { op, lhs: Box::new(lhs), rhs: Box::new(rhs) };
         }
+
+        Ok(lhs)
     }
 
-    pub fn unary(&mut self) {
-        if self.check_token(lex::TokenType::PLUS) || self.check_token(lex::TokenType::MINUS) {
-            self.emitter.emit(self.cur_token.get_text().as_str());
+    pub fn unary(&mut self) -> Result<ast::Expression, CompileError> {
+        let op = match self.cur_token.kind {
+            lex::TokenType::PLUS => Some(ast::UnaryOp::Plus),
+            lex::TokenType::MINUS => Some(ast::UnaryOp::Minus),
+            _ => None,
+        };
+
+        let operator = self.cur_token;
+        if op.is_some() {
             self.next_token();
         }
 
-        self.primary();
+        let operand = self.power()?;
+        if op.is_some() && operand.is_text() {
+            let error = CompileError::Semantic {
+                message: format!("Operator {} is not defined for strings", operator.text),
+                span: operator.span,
+                notes: Vec::new(),
+                help: None,
+            };
+            self.report(error);
+        }
+
+        match op {
+            Some(op) => Ok(ast::Expression::Unary { op, operand: Box::new(operand) }),
+            None => Ok(operand),
+        }
     }
 
-    pub fn primary(&mut self) {
+    /// `^` binds tighter than a leading sign and groups to the right, so
+    /// `-2 ^ 2` is -4 and `2 ^ 3 ^ 2` is 2 ^ 9.
+    pub fn power(&mut self) -> Result<ast::Expression, CompileError> {
+        let lhs = self.primary()?;
+        if !self.check_token(lex::TokenType::CARET) {
+            return Ok(lhs);
+        }
+
+        let operator = self.cur_token;
+        self.next_token();
+        let rhs = self.unary()?;
+        self.check_operands(operator, ast::BinaryOp::Pow, &lhs, &rhs);
+
+        Ok(ast::Expression::Binary { op: ast::BinaryOp::Pow, lhs: Box::new(lhs), rhs: Box::new(rhs) })
+    }
+
+    pub fn primary(&mut self) -> Result<ast::Expression, CompileError> {
         if self.check_token(lex::TokenType::NUMBER) {
-            self.emitter.emit(self.cur_token.get_text().as_str());
+            let text = self.cur_token.text.to_string();
+            self.next_token();
+
+            Ok(ast::Expression::Number(text))
+        } else if self.check_token(lex::TokenType::STRING) {
+            let text = lex::unescape(self.cur_token.text);
+            self.next_token();
+
+            Ok(ast::Expression::Text(text))
+        } else if self.check_token(lex::TokenType::LPAREN) {
             self.next_token();
+
+            self.paren_depth += 1;
+            let inner = self.condition();
+            self.paren_depth -= 1;
+
+            let inner = inner?;
+            self.match_token(lex::TokenType::RPAREN)?;
+            Ok(ast::Expression::Group(Box::new(inner)))
         } else if self.check_token(lex::TokenType::IDENT) {
 
-            let token_text = self.cur_token.get_text();
-            if !self.symbols.contains(&token_text) {
-                panic!("[PARSER] Error: Refencing variable {token_text} before assignment");
+            let token = self.cur_token;
+            if matches!(self.tokens.peek(), Some(Ok(next)) if next.kind == lex::TokenType::LPAREN) {
+                self.next_token();
+
+                if let Some(function) = ast::Intrinsic::from_name(token.text) {
+                    let args = self.expression_list()?;
+                    if args.len() != function.arity() {
+                        let error = CompileError::Semantic {
+                            message: format!("Function {} expects {}, got {}", token.text, plural(function.arity(), "argument", "arguments"), args.len()),
+                            span: token.span,
+                            notes: Vec::new(),
+                            help: None,
+                        };
+                        self.report(error);
+                    }
+                    return Ok(ast::Expression::Intrinsic { function, args });
+                }
+
+                if self.scope.arrays.contains_key(token.text) {
+                    return Ok(ast::Expression::Element(self.element(token)?));
+                }
+
+                let args = self.expression_list()?;
+                self.calls.push(Call { name: token.text, arity: args.len(), span: token.span });
+                return Ok(ast::Expression::Call { name: token.text.to_string(), args });
+            }
+
+            let token_text = token.text;
+            if self.scope.arrays.contains_key(token_text) {
+                self.check_scalar(token);
+            } else if !self.scope.symbols.contains(token_text) {
+                let error = self.semantic_error(
+                    format!("Referencing variable {token_text} before assignment"),
+                    Some(format!("assign a value first with `LET {token_text} = ...` or `INPUT {token_text}`")),
+                );
+                self.report(error);
             }
 
-            self.emitter.emit(self.cur_token.get_text().as_str());
             self.next_token();
+            Ok(ast::Expression::Variable(token_text.to_string()))
         } else {
             let current = self.cur_token.kind.to_string();
-            panic!("[PARSER] Error: Unexpected token at {current}");
+            Err(self.syntax_error(format!("Unexpected token at {current}")))
         }
     }
 }
+
+fn plural(count: usize, singular: &str, plural: &str) -> String {
+    match count {
+        1 => format!("1 {singular}"),
+        count => format!("{count} {plural}"),
+    }
+}
+
+fn type_name(text: bool) -> &'static str {
+    match text {
+        true => "string",
+        false => "number",
+    }
+}
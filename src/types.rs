@@ -0,0 +1,258 @@
+use std::collections::{HashMap, HashSet};
+use crate::ast;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Type {
+    Integer,
+    Float,
+    Text,
+}
+
+impl From<ast::NumericType> for Type {
+    fn from(numeric_type: ast::NumericType) -> Self {
+        match numeric_type {
+            ast::NumericType::Integer => Type::Integer,
+            ast::NumericType::Float => Type::Float,
+        }
+    }
+}
+
+/// Anything that holds a number. `None` as the scope is the main program,
+/// `Some(name)` the body of a FUNCTION.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Slot {
+    Variable(Option<String>, String),
+    Array(Option<String>, String),
+    Return(String),
+}
+
+/// A value stored into a slot, evaluated in the scope it appears in.
+struct Assignment<'p> {
+    slot: Slot,
+    scope: Option<&'p str>,
+    value: &'p ast::Expression,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Types {
+    pub slots: HashMap<Slot, Type>,
+}
+
+impl Types {
+
+    /// Every slot starts out as an integer and becomes a float as soon as a
+    /// float is stored into it, until nothing changes. `AS` annotations pin
+    /// a slot and INPUT reads floats unless told otherwise.
+    pub fn infer(program: &ast::Program) -> Self {
+        let mut inference = Inference {
+            functions: program.functions.iter()
+                .map(|function| (function.name.as_str(), function))
+                .collect(),
+            ..Inference::default()
+        };
+
+        inference.statements(&program.statements, None);
+        for function in program.functions.iter() {
+            let scope = Some(function.name.as_str());
+
+            for (param, numeric_type) in function.params.iter().zip(function.param_types.iter()) {
+                if let Some(numeric_type) = numeric_type {
+                    inference.pin(Slot::Variable(scope.map(String::from), param.clone()), Type::from(*numeric_type));
+                }
+            }
+            if let Some(numeric_type) = function.return_type {
+                inference.pin(Slot::Return(function.name.clone()), Type::from(numeric_type));
+            }
+            inference.statements(&function.body, scope);
+        }
+
+        let mut types = Types::default();
+        types.slots.extend(inference.pinned.iter().map(|(slot, slot_type)| (slot.clone(), *slot_type)));
+        for slot in inference.inputs {
+            types.slots.entry(slot).or_insert(Type::Float);
+        }
+
+        loop {
+            let mut changed = false;
+            for assignment in inference.assignments.iter() {
+                if inference.pinned.contains_key(&assignment.slot) || types.slot(&assignment.slot) == Type::Float {
+                    continue;
+                }
+
+                if types.of(assignment.scope, assignment.value) == Type::Float {
+                    types.slots.insert(assignment.slot.clone(), Type::Float);
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                return types;
+            }
+        }
+    }
+
+    pub fn slot(&self, slot: &Slot) -> Type {
+        self.slots.get(slot).copied().unwrap_or(Type::Integer)
+    }
+
+    pub fn variable(&self, scope: Option<&str>, name: &str) -> Type {
+        if name.ends_with('$') {
+            return Type::Text;
+        }
+        self.slot(&Slot::Variable(scope.map(String::from), name.to_string()))
+    }
+
+    pub fn array(&self, scope: Option<&str>, name: &str) -> Type {
+        self.slot(&Slot::Array(scope.map(String::from), name.to_string()))
+    }
+
+    pub fn returns(&self, function: &str) -> Type {
+        self.slot(&Slot::Return(function.to_string()))
+    }
+
+    pub fn of(&self, scope: Option<&str>, expression: &ast::Expression) -> Type {
+        match expression {
+            ast::Expression::Number(text) if text.parse::<i64>().is_ok() => Type::Integer,
+            ast::Expression::Number(_) => Type::Float,
+            ast::Expression::Text(_) => Type::Text,
+            ast::Expression::Variable(name) => self.variable(scope, name),
+            ast::Expression::Element(element) => self.array(scope, &element.array),
+            ast::Expression::Call { name, .. } => self.returns(name),
+            ast::Expression::Intrinsic { function, args } => match function {
+                ast::Intrinsic::Abs => args.first().map_or(Type::Float, |arg| self.of(scope, arg)),
+                ast::Intrinsic::Int => Type::Integer,
+                _ => Type::Float,
+            },
+            ast::Expression::Unary { op: ast::UnaryOp::Not, .. } => Type::Integer,
+            ast::Expression::Unary { operand, .. } => self.of(scope, operand),
+            ast::Expression::Group(inner) => self.of(scope, inner),
+            ast::Expression::Binary { op, lhs, rhs } => match op {
+                ast::BinaryOp::Div | ast::BinaryOp::Pow => Type::Float,
+                op if op.is_arithmetic() => match (self.of(scope, lhs), self.of(scope, rhs)) {
+                    (Type::Text, _) => Type::Text,
+                    (Type::Integer, Type::Integer) => Type::Integer,
+                    _ => Type::Float,
+                },
+                _ => Type::Integer,
+            },
+        }
+    }
+}
+
+#[derive(Default)]
+struct Inference<'p> {
+    functions: HashMap<&'p str, &'p ast::Function>,
+    assignments: Vec<Assignment<'p>>,
+    pinned: HashMap<Slot, Type>,
+    inputs: HashSet<Slot>,
+}
+
+impl<'p> Inference<'p> {
+
+    fn pin(&mut self, slot: Slot, slot_type: Type) {
+        self.pinned.insert(slot, slot_type);
+    }
+
+    fn assign(&mut self, slot: Slot, scope: Option<&'p str>, value: &'p ast::Expression) {
+        self.expression(value, scope);
+        self.assignments.push(Assignment { slot, scope, value });
+    }
+
+    fn statements(&mut self, statements: &'p [ast::Statement], scope: Option<&'p str>) {
+        let variable = |name: &str| Slot::Variable(scope.map(String::from), name.to_string());
+
+        for statement in statements.iter() {
+            match statement {
+                ast::Statement::Print { items, .. } => {
+                    for item in items.iter() {
+                        if let ast::Printable::Expr(value) = item {
+                            self.expression(value, scope);
+                        }
+                    }
+                },
+                ast::Statement::Randomize(value) => self.expression(value, scope),
+                ast::Statement::Label(_)
+                    | ast::Statement::Goto(_)
+                    | ast::Statement::Gosub { .. }
+                    | ast::Statement::Return { value: None, .. } => (),
+                ast::Statement::If { condition, body, else_ifs, else_body } => {
+                    self.expression(condition, scope);
+                    self.statements(body, scope);
+                    for else_if in else_ifs.iter() {
+                        self.expression(&else_if.condition, scope);
+                        self.statements(&else_if.body, scope);
+                    }
+                    if let Some(else_body) = else_body {
+                        self.statements(else_body, scope);
+                    }
+                },
+                ast::Statement::While { condition, body } => {
+                    self.expression(condition, scope);
+                    self.statements(body, scope);
+                },
+                ast::Statement::For { variable: name, start, end, step, body } => {
+                    self.assign(variable(name), scope, start);
+                    self.expression(end, scope);
+                    if let Some(step) = step {
+                        self.assign(variable(name), scope, step);
+                    }
+                    self.statements(body, scope);
+                },
+                ast::Statement::Return { value: Some(value), .. } => {
+                    if let Some(function) = scope {
+                        self.assign(Slot::Return(function.to_string()), scope, value);
+                    }
+                },
+                ast::Statement::Let { name, value } => self.assign(variable(name), scope, value),
+                ast::Statement::Dim { name, bounds, numeric_type } => {
+                    let slot = match bounds.is_empty() {
+                        true => variable(name),
+                        false => Slot::Array(scope.map(String::from), name.clone()),
+                    };
+                    if let Some(numeric_type) = numeric_type {
+                        self.pin(slot, Type::from(*numeric_type));
+                    }
+                },
+                ast::Statement::SetElement { element, value } => {
+                    for index in element.indices.iter() {
+                        self.expression(index, scope);
+                    }
+                    self.assign(Slot::Array(scope.map(String::from), element.array.clone()), scope, value);
+                },
+                ast::Statement::Input { names, .. } => {
+                    self.inputs.extend(names.iter().map(|name| variable(name)));
+                },
+            }
+        }
+    }
+
+    /// Records the arguments of every call as assignments to the parameters.
+    fn expression(&mut self, expression: &'p ast::Expression, scope: Option<&'p str>) {
+        match expression {
+            ast::Expression::Number(_)
+                | ast::Expression::Text(_)
+                | ast::Expression::Variable(_) => (),
+            ast::Expression::Element(element) => {
+                for index in element.indices.iter() {
+                    self.expression(index, scope);
+                }
+            },
+            ast::Expression::Call { name, args } => {
+                let params = self.functions.get(name.as_str()).map(|function| &function.params);
+                for (param, arg) in params.into_iter().flatten().zip(args.iter()) {
+                    self.assign(Slot::Variable(Some(name.clone()), param.clone()), scope, arg);
+                }
+            },
+            ast::Expression::Intrinsic { args, .. } => {
+                for arg in args.iter() {
+                    self.expression(arg, scope);
+                }
+            },
+            ast::Expression::Unary { operand, .. } | ast::Expression::Group(operand) => self.expression(operand, scope),
+            ast::Expression::Binary { lhs, rhs, .. } => {
+                self.expression(lhs, scope);
+                self.expression(rhs, scope);
+            },
+        }
+    }
+}
@@ -1,18 +1,64 @@
 use std::fs::File;
 use std::io::Read;
 use std::path::PathBuf;
+use crate::error::CompileError;
 
-pub fn read_file(filename: &PathBuf) -> Vec<char> {
-    let mut f = match File::open(filename) {
-        Ok(file) => file,
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+pub fn read_file(filename: &PathBuf) -> Result<String, CompileError> {
+    let path = filename.display();
+    let mut f = File::open(filename)
+        .map_err(|err| CompileError::Io(format!("{err} | {path}")))?;
+
+    let mut raw: Vec<u8> = Vec::new();
+    f.read_to_end(&mut raw)
+        .map_err(|err| CompileError::Io(format!("{err} | {path}")))?;
+
+    decode_source(&raw)
+        .map_err(|err| CompileError::Io(format!("{err} | {path}")))
+}
+
+/// Decodes UTF-8 source, honouring an optional UTF-8 BOM. UTF-16 is
+/// accepted only when the input starts with a byte order mark.
+pub fn decode_source(raw: &[u8]) -> Result<String, String> {
+    if let Some(rest) = raw.strip_prefix(UTF8_BOM) {
+        decode_utf8(rest)
+    } else if let Some(rest) = raw.strip_prefix(UTF16LE_BOM) {
+        decode_utf16(rest, u16::from_le_bytes)
+    } else if let Some(rest) = raw.strip_prefix(UTF16BE_BOM) {
+        decode_utf16(rest, u16::from_be_bytes)
+    } else {
+        decode_utf8(raw)
+    }
+}
+
+fn decode_utf8(raw: &[u8]) -> Result<String, String> {
+    match std::str::from_utf8(raw) {
+        Ok(source) => Ok(source.to_string()),
         Err(err) => {
-            let path = filename.display();
-            panic!("[UTILS] {err} | {path}");
+            let valid = &raw[..err.valid_up_to()];
+            let line = valid.iter().filter(|b| **b == b'\n').count() + 1;
+            let line_start = valid.iter().rposition(|b| *b == b'\n').map_or(0, |pos| pos + 1);
+            let column = String::from_utf8_lossy(&valid[line_start..]).chars().count() + 1;
+
+            Err(format!(
+                "Invalid UTF-8 byte 0x{:02X} at line {line}, column {column}",
+                raw[err.valid_up_to()],
+            ))
         },
-    };
+    }
+}
 
-    let mut raw: Vec<u8> = Vec::new();
-    let _ = f.read_to_end(&mut raw);
-    
-    raw.iter().map(|b| *b as char).collect::<Vec<_>>()
+fn decode_utf16(raw: &[u8], from_bytes: fn([u8; 2]) -> u16) -> Result<String, String> {
+    if !raw.len().is_multiple_of(2) {
+        return Err(String::from("Invalid UTF-16: odd number of bytes"));
+    }
+
+    let units = raw.chunks_exact(2).map(|pair| from_bytes([pair[0], pair[1]]));
+
+    char::decode_utf16(units)
+        .collect::<Result<String, _>>()
+        .map_err(|err| format!("Invalid UTF-16: unpaired surrogate 0x{:04X}", err.unpaired_surrogate()))
 }
@@ -0,0 +1,60 @@
+use simple_compiler::{lex, parse, error, diagnostics};
+
+fn render(source: &str) -> String {
+    let mut parser = parse::Parser::new(lex::Lexer::new(source));
+    let errors = parser.program().unwrap_err();
+
+    diagnostics::Diagnostic::from(&errors[0]).render("test.tb", source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_semantic() {
+        let res = render("LET a = 1\nPRINT a + b\n");
+        let expected = concat!(
+            "error[semantic]: Referencing variable b before assignment\n",
+            " --> test.tb:2:11\n",
+            "  |\n",
+            "2 | PRINT a + b\n",
+            "  |           ^\n",
+            "  |\n",
+            "  = help: assign a value first with `LET b = ...` or `INPUT b`\n",
+        );
+
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn test_render_underline() {
+        let res = render("LET a = 1\nGOTO missing\n");
+        let expected = concat!(
+            "error[semantic]: GOTO to undeclared label missing\n",
+            " --> test.tb:2:6\n",
+            "  |\n",
+            "2 | GOTO missing\n",
+            "  |      ^^^^^^^\n",
+            "  |\n",
+            "  = help: declare it somewhere in the program with `LABEL missing`\n",
+        );
+
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn test_render_lexical() {
+        let res = render("PRINT \"ok\"\n\tLET a = 5 $ 2\n");
+        assert!(res.starts_with("error[lexical]: Unknown token $\n --> test.tb:2:12\n"));
+        assert!(res.contains("2 | \tLET a = 5 $ 2\n  | \t          ^\n"));
+    }
+
+    #[test]
+    fn test_render_without_span() {
+        let error = error::CompileError::Io(String::from("No such file or directory"));
+        let res = diagnostics::Diagnostic::from(&error).render("test.tb", "");
+
+        assert_eq!(res, "error[io]: No such file or directory\n");
+    }
+}
@@ -1,32 +1,23 @@
-#[path = "../src/lex.rs"] mod lex;
-#[path = "../src/parse.rs"] mod parse;
-#[path = "../src/emitter.rs"] mod emitter;
-#[path = "../src/utils/utils.rs"] mod utils;
+use simple_compiler::{lex, parse, codegen, emitter, utils};
 
 use std::path::PathBuf;
 use utils::read_file;
 
-fn compile(filename: &str) {
+fn compile(filename: &str) -> emitter::Emitter {
 
     let filepath = PathBuf::from(filename);
-    let file = String::from(filepath.file_stem().unwrap().to_str().unwrap()) + ".c";
 
-    let mut parser = parse::Parser::new(
-        lex::Lexer::new(utils::read_file(&filepath)),
-        emitter::Emitter::new(PathBuf::from(file)),
-    );
+    let source = read_file(&filepath).unwrap();
+    let mut parser = parse::Parser::new(lex::Lexer::new(&source));
+    let program = parser.program().unwrap();
 
-    parser.emit();
+    let mut generator = codegen::CodeGenerator::new(emitter::Emitter::new());
+    generator.generate(&program);
+    generator.emitter
 }
 
-fn compare_files(filename: &str) -> bool {
-
-    let filepath = PathBuf::from(filename);
-   
-    let o = read_file(&PathBuf::from(filepath.file_name().unwrap()));
-    let i = read_file(&filepath);
-
-    o == i
+fn expected(filename: &str) -> String {
+    read_file(&PathBuf::from(filename)).unwrap()
 }
 
 #[cfg(test)]
@@ -35,13 +26,83 @@ mod tests {
 
     #[test]
     fn test_average() {
-        compile("src/examples/code/source/average.tb");
-        assert!(compare_files("src/examples/code/built/average.c"));
+        let res = compile("src/examples/code/source/average.tb");
+        assert_eq!(res.into_string(), expected("src/examples/code/built/average.c"));
     }
 
     #[test]
     fn test_fibonacci() {
-        compile("src/examples/code/source/fibonacci.tb");
-        assert!(compare_files("src/examples/code/built/fibonacci.c"));
+        let res = compile("src/examples/code/source/fibonacci.tb");
+        assert_eq!(res.into_string(), expected("src/examples/code/built/fibonacci.c"));
+    }
+
+    #[test]
+    fn test_grade() {
+        let res = compile("src/examples/code/source/grade.tb");
+        assert_eq!(res.into_string(), expected("src/examples/code/built/grade.c"));
+    }
+
+    #[test]
+    fn test_countdown() {
+        let res = compile("src/examples/code/source/countdown.tb");
+        assert_eq!(res.into_string(), expected("src/examples/code/built/countdown.c"));
+    }
+
+    #[test]
+    fn test_squares() {
+        let res = compile("src/examples/code/source/squares.tb");
+        assert_eq!(res.into_string(), expected("src/examples/code/built/squares.c"));
+    }
+
+    #[test]
+    fn test_functions() {
+        let res = compile("src/examples/code/source/functions.tb");
+        assert_eq!(res.into_string(), expected("src/examples/code/built/functions.c"));
+    }
+
+    #[test]
+    fn test_matrix() {
+        let res = compile("src/examples/code/source/matrix.tb");
+        assert_eq!(res.into_string(), expected("src/examples/code/built/matrix.c"));
+    }
+
+    #[test]
+    fn test_greeting() {
+        let res = compile("src/examples/code/source/greeting.tb");
+        assert_eq!(res.into_string(), expected("src/examples/code/built/greeting.c"));
+    }
+
+    #[test]
+    fn test_primes() {
+        let res = compile("src/examples/code/source/primes.tb");
+        assert_eq!(res.into_string(), expected("src/examples/code/built/primes.c"));
+    }
+
+    #[test]
+    fn test_dice() {
+        let res = compile("src/examples/code/source/dice.tb");
+        assert_eq!(res.into_string(), expected("src/examples/code/built/dice.c"));
+    }
+
+    #[test]
+    fn test_table() {
+        let res = compile("src/examples/code/source/table.tb");
+        assert_eq!(res.into_string(), expected("src/examples/code/built/table.c"));
+    }
+
+    #[test]
+    fn test_typed() {
+        let res = compile("src/examples/code/source/typed.tb");
+        assert_eq!(res.into_string(), expected("src/examples/code/built/typed.c"));
+    }
+
+    #[test]
+    fn test_write_to() {
+        let res = compile("src/examples/code/source/fibonacci.tb");
+
+        let mut sink: Vec<u8> = Vec::new();
+        res.write_to(&mut sink).unwrap();
+
+        assert_eq!(String::from_utf8(sink).unwrap(), expected("src/examples/code/built/fibonacci.c"));
     }
 }
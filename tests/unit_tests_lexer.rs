@@ -1,21 +1,16 @@
-#[path = "../src/lex.rs"] mod lex;
-#[path = "../src/utils/utils.rs"] mod utils;
+use simple_compiler::{lex, error, utils};
 
 use std::path::PathBuf;
 
-fn get_tokens(filename: &str) -> Vec<lex::Token> {
-    let mut lexer = lex::Lexer::new(
-        utils::read_file(&PathBuf::from(filename))
-    );
-    let mut token = lexer.get_token();
-    let mut res: Vec<lex::Token> = Vec::new();
-
-    while !matches!(token.kind, lex::TokenType::EOF) {
-        res.push(token);
-        token = lexer.get_token();
-    }
+fn read_source(filename: &str) -> String {
+    utils::read_file(&PathBuf::from(filename)).unwrap()
+}
 
-    res
+fn get_tokens(source: &str) -> Vec<lex::Token<'_>> {
+    lex::Lexer::new(source)
+        .map(Result::unwrap)
+        .take_while(|token| token.kind != lex::TokenType::EOF)
+        .collect()
 }
 
 #[cfg(test)]
@@ -24,15 +19,29 @@ mod tests {
 
     #[test]
     fn test_strings() {
-        let res = get_tokens("src/examples/tests/test_1.txt");
+        let source = read_source("src/examples/tests/test_1.txt");
+        let res = get_tokens(&source);
         assert!(matches!(res[0].kind, lex::TokenType::STRING));
         assert!(matches!(res[1].kind, lex::TokenType::STRING));
         assert!(matches!(res[2].kind, lex::TokenType::STRING));
     }
 
+    #[test]
+    fn test_token_text() {
+        let source = read_source("src/examples/tests/test_1.txt");
+        let res = get_tokens(&source);
+
+        assert_eq!(res[0].text, "asas ");
+        assert_eq!(res[1].text, "1231was13");
+        assert_eq!(res[2].text, "GOTO");
+        // Token text is a slice of the source, not a copy.
+        assert!(std::ptr::eq(res[0].text.as_ptr(), source[1..].as_ptr()));
+    }
+
     #[test]
     fn test_operators() {
-        let res = get_tokens("src/examples/tests/test_2.txt");
+        let source = read_source("src/examples/tests/test_2.txt");
+        let res = get_tokens(&source);
         assert!(matches!(res[0].kind, lex::TokenType::PLUS));
         assert!(matches!(res[1].kind, lex::TokenType::GTEQ));
         assert!(matches!(res[2].kind, lex::TokenType::EQEQ));
@@ -41,10 +50,74 @@ mod tests {
 
     #[test]
     fn test_ident() {
-        let res = get_tokens("src/examples/tests/test_3.txt");
+        let source = read_source("src/examples/tests/test_3.txt");
+        let res = get_tokens(&source);
         assert!(matches!(res[0].kind, lex::TokenType::GOTO));
         assert!(matches!(res[1].kind, lex::TokenType::IDENT));
         assert!(matches!(res[2].kind, lex::TokenType::IDENT));
         assert!(matches!(res[3].kind, lex::TokenType::IF));
     }
+
+    #[test]
+    fn test_string_ident() {
+        let res = get_tokens("INPUT name$\nLET a$b = 1\n");
+        assert_eq!((res[1].kind, res[1].text), (lex::TokenType::IDENT, "name$"));
+        assert_eq!((res[4].kind, res[4].text), (lex::TokenType::IDENT, "a$"));
+        assert_eq!((res[5].kind, res[5].text), (lex::TokenType::IDENT, "b"));
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        let res = get_tokens("PRINT \"a\\\"b\\\\c\\n\td%\"\n");
+        assert_eq!((res[1].kind, res[1].text), (lex::TokenType::STRING, "a\\\"b\\\\c\\n\td%"));
+        assert_eq!(lex::unescape(res[1].text), "a\"b\\c\n\td%");
+
+        let res = lex::Lexer::new("PRINT \"a\\qb\"\nPRINT 1\n").collect::<Vec<_>>();
+        match &res[1] {
+            Err(error::CompileError::Lexical { message, span }) => {
+                assert_eq!(message, "Unknown escape sequence \\q in string.");
+                assert_eq!((span.column, span.length), (9, 2));
+            },
+            other => panic!("expected a lexical error, got {other:?}"),
+        }
+        assert!(matches!(res[2], Ok(lex::Token { kind: lex::TokenType::NEWLINE, .. })));
+    }
+
+    #[test]
+    fn test_lexical_error() {
+        let lexer = lex::Lexer::new("LET a = !5");
+        let res = lexer.collect::<Vec<_>>();
+
+        assert!(matches!(res[3], Err(error::CompileError::Lexical { .. })));
+        assert!(matches!(res[4], Ok(lex::Token { kind: lex::TokenType::NUMBER, .. })));
+    }
+
+    #[test]
+    fn test_iterator() {
+        let mut lexer = lex::Lexer::new("PRINT 1\n").peekable();
+
+        assert!(matches!(lexer.peek(), Some(Ok(lex::Token { kind: lex::TokenType::PRINT, .. }))));
+        assert_eq!(lexer.by_ref().filter_map(Result::ok).count(), 4);
+        assert!(lexer.next().is_none());
+    }
+
+    #[test]
+    fn test_spans() {
+        let source = read_source("src/examples/tests/test_6.txt");
+        let res = get_tokens(&source);
+        let span = |index: usize| {
+            let span = res[index].span;
+            (span.offset, span.line, span.column, span.length)
+        };
+
+        // LET foo = 6 * 3 + 2
+        assert_eq!(span(0), (0, 1, 1, 3));
+        assert_eq!(span(1), (4, 1, 5, 3));
+        // IF foo > 0 THEN
+        assert!(matches!(res[9].kind, lex::TokenType::IF));
+        assert_eq!(span(9), (20, 2, 1, 2));
+        // PRINT "yes!"
+        assert!(matches!(res[16].kind, lex::TokenType::STRING));
+        assert_eq!(span(16), (46, 3, 11, 6));
+    }
 }
@@ -0,0 +1,255 @@
+use simple_compiler::{codegen, compile_str, compile_to, error, lex, tokenize, CompileOptions};
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Builds `source` with gcc and runs it on `input`, returning its output.
+fn run(name: &str, source: &str, input: &str) -> String {
+    let dir = std::env::temp_dir();
+    let c_file = dir.join(format!("simple_compiler_{name}.c"));
+    let binary = dir.join(format!("simple_compiler_{name}"));
+
+    std::fs::write(&c_file, compile_str(source, &CompileOptions::default()).unwrap()).unwrap();
+    let status = Command::new("gcc").arg("-o").arg(&binary).arg(&c_file).arg("-lm").status().unwrap();
+    assert!(status.success());
+
+    let mut child = Command::new(&binary)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    String::from_utf8(child.wait_with_output().unwrap().stdout).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compile_str() {
+        let res = compile_str("LET a = 2\nPRINT a * 3\n", &CompileOptions::default()).unwrap();
+        let expected = concat!(
+            "#include <stdio.h>\n",
+            "int main(void){\n",
+            "long long a_num;\n",
+            "a_num = 2;\n",
+            "printf(\"%lld\\n\", (long long)(a_num*3));\n",
+            "return 0;\n",
+            "}\n",
+        );
+
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn test_compile_str_types() {
+        let res = compile_str("LET a = 1\nLET b = a / 2\nLET c = a MOD 2\nINPUT d\nDIM e AS INTEGER\nINPUT e\n", &CompileOptions::default()).unwrap();
+        assert!(res.contains("long long a_num;\ndouble b_num;\nlong long c_num;\ndouble d_num;\nlong long e_num;\n"));
+        assert!(res.contains("b_num = a_num/(double)2;\nc_num = a_num%2;\n"));
+        assert!(res.contains("scanf(\"%lf\", &d_num)"));
+        assert!(res.contains("e_num = 0;\nif(0 == scanf(\"%lld\", &e_num)"));
+
+        let res = compile_str("LET a = 9223372036854775807\nLET b = 9223372036854775808\n", &CompileOptions::default()).unwrap();
+        assert!(res.contains("long long a_num;\ndouble b_num;\n"));
+        assert!(res.contains("a_num = 9223372036854775807;\nb_num = 9223372036854775808.0;\n"));
+
+        let res = compile_str("FUNCTION f(x AS FLOAT) AS INTEGER\nRETURN x\nENDFUNCTION\nPRINT f(1) + ABS(-2)\n", &CompileOptions::default()).unwrap();
+        assert!(res.contains("long long fn_f(double x_num){\n"));
+        assert!(res.contains("printf(\"%lld\\n\", (long long)(fn_f(1)+llabs(-2)));\n"));
+    }
+
+    #[test]
+    fn test_compile_str_mod_check() {
+        let res = compile_str("LET a = 7\nLET b = a MOD 2\n", &CompileOptions::default()).unwrap();
+        assert!(res.contains("b_num = a_num%2;\n"));
+        assert!(!res.contains("mod_check"));
+
+        let res = compile_str("LET a = 7\nLET b = a MOD 0 + a MOD (a - 7)\n", &CompileOptions::default()).unwrap();
+        assert!(res.contains("#include <stdlib.h>\n"));
+        assert!(res.contains("static long long mod_check(long long divisor){\n"));
+        assert!(res.contains("b_num = a_num%mod_check(0)+a_num%mod_check((a_num-7));\n"));
+    }
+
+    #[test]
+    fn test_compile_str_print_list() {
+        let res = compile_str("LET a$ = \"x\"\nPRINT \"a=\"; a$; 1.5;\nPRINT 2\n", &CompileOptions::default()).unwrap();
+        assert!(res.contains("printf(\"%s%s%.2f\", \"a=\", a_str.text, (double)(1.5));\nprintf(\"%lld\\n\", (long long)(2));\n"));
+        assert!(!res.contains("print_column"));
+    }
+
+    #[test]
+    fn test_compile_str_print_zones() {
+        let res = compile_str("PRINT \"a\", \"b\";\nINPUT \"x? \"; x\nPRINT \"c\", \"d\"\n", &CompileOptions::default()).unwrap();
+        assert!(res.contains("print_column += printf(\"%s\", \"b\");\nprint_column += printf(\"%s\", \"x? \");\n"));
+        assert!(res.contains("}\nprint_column = 0;\nprint_column += printf(\"%s\", \"c\");\n"));
+    }
+
+    #[test]
+    fn test_compile_str_print_using() {
+        let res = compile_str("LET a = 7\nPRINT USING \"+000.#\"; a; a / 2\n", &CompileOptions::default()).unwrap();
+        assert!(res.contains("printf(\"%+06.1f%+06.1f\\n\", (double)(a_num), (double)(a_num/(double)2));\n"));
+    }
+
+    #[test]
+    fn test_compile_str_invalid_input() {
+        let source = "INPUT \"a, b? \"; a, b\n";
+
+        let res = compile_str(source, &CompileOptions::default()).unwrap();
+        assert!(res.contains("fputs(\"a, b? \", stdout);\nif(0 == scanf(\"%lf\", &a_num)) {\na_num = 0;\nscanf(\"%*s\");\n}\n"));
+
+        let options = CompileOptions { invalid_input: codegen::InvalidInput::Retry, ..CompileOptions::default() };
+        let res = compile_str(source, &options).unwrap();
+        assert!(res.contains("for (;;) {\nfputs(\"a, b? \", stdout);\nif (1 != scanf(\"%lf\", &a_num)) {\ninput_retry();\ncontinue;\n}\n"));
+        assert!(res.contains("continue;\n}\nbreak;\n}\n"));
+
+        let options = CompileOptions { invalid_input: codegen::InvalidInput::Abort, ..CompileOptions::default() };
+        let res = compile_str(source, &options).unwrap();
+        assert!(res.contains("if (1 != scanf(\"%lf\", &b_num)) {\nfprintf(stderr, \"Runtime error: invalid input for b\\n\");\nexit(1);\n}\n"));
+    }
+
+    #[test]
+    fn test_run_mixed_input() {
+        let source = "INPUT a, n$\nPRINT a; \"[\"; n$; \"]\"\n";
+        assert_eq!(run("mixed_input", source, "3 Bob\n"), "3.00[Bob]\n");
+        assert_eq!(run("mixed_input_lines", source, "3\n  Bob\n"), "3.00[Bob]\n");
+    }
+
+    #[test]
+    fn test_compile_str_escapes() {
+        let res = compile_str("PRINT \"50% \\\"off\\\"\\t??!\\\\\"; 1\nLET a$ = \"%s\\n\"\n", &CompileOptions::default()).unwrap();
+        assert!(res.contains("printf(\"%s%lld\\n\", \"50% \\\"off\\\"\\t?\\?!\\\\\", (long long)(1));\n"));
+        assert!(res.contains("a_str = string_from(\"%s\\n\");\n"));
+    }
+
+    #[test]
+    fn test_compile_str_for_limits() {
+        let res = compile_str("LET i = 10\nFOR i = 1 TO i STEP RND(3)\nNEXT i\nFOR j = 5 TO 1 STEP -1\nNEXT j\n", &CompileOptions::default()).unwrap();
+        assert!(res.contains("double i_num;\ndouble for_end_0;\ndouble for_step_0;\n"));
+        assert!(res.contains("for_end_0 = i_num;\nfor_step_0 = rnd(3);\nfor (i_num = 1; for_step_0>=0 ? i_num<=for_end_0 : i_num>=for_end_0; i_num = i_num+for_step_0) {\n"));
+        assert!(res.contains("for (j_num = 5; j_num>=1; j_num = j_num-1) {\n"));
+    }
+
+    #[test]
+    fn test_compile_str_string_helpers() {
+        let res = compile_str("INPUT a$\nPRINT a$\n", &CompileOptions::default()).unwrap();
+        assert!(res.contains("static string_t string_input(int skip_blanks){\n"));
+        assert!(res.contains("a_str = string_input(0);\n"));
+        assert!(!res.contains("string.h"));
+        assert!(!res.contains("string_from"));
+        assert!(!res.contains("string_concat"));
+        assert!(!res.contains("string_equal"));
+
+        let res = compile_str("LET a$ = \"x\" + \"y\"\n", &CompileOptions::default()).unwrap();
+        assert!(res.contains("#include <string.h>\n"));
+        assert!(res.contains("static string_t string_concat(string_t a, string_t b){\n"));
+        assert!(!res.contains("string_equal"));
+    }
+
+    #[test]
+    fn test_compile_str_reserved_names() {
+        let res = compile_str("LET sqrt = 9\nLET rnd = 2\nDIM exit(1)\nPRINT SQRT(sqrt) + RND(rnd) + exit(0)\n", &CompileOptions::default()).unwrap();
+        assert!(res.contains("long long sqrt_num;\nlong long rnd_num;\n"));
+        assert!(res.contains("(sqrt(sqrt_num)+rnd(rnd_num)+exit_arr[(int)(0)])"));
+
+        let res = compile_str("GOSUB int\nGOTO a$\nLABEL int\nRETURN\nLABEL a$\n", &CompileOptions::default()).unwrap();
+        assert!(res.contains("goto lbl_int;\n"));
+        assert!(res.contains("goto lbl_a_str;\nlbl_int:\n"));
+        assert!(res.contains("lbl_a_str:\n"));
+    }
+
+    #[test]
+    fn test_compile_to() {
+        let mut sink: Vec<u8> = Vec::new();
+        compile_to("PRINT \"hi\"\n", &CompileOptions::default(), &mut sink).unwrap();
+
+        assert!(String::from_utf8(sink).unwrap().contains("fputs(\"hi\\n\", stdout);\n"));
+    }
+
+    #[test]
+    fn test_compile_str_boolean_operators() {
+        let res = compile_str("LET a = 1\nIF a > 1 OR a < 5 AND NOT a == 3 THEN\nENDIF\n", &CompileOptions::default()).unwrap();
+        assert!(res.contains("if (a_num>1||(a_num<5&&!(a_num==3))) {\n"));
+    }
+
+    #[test]
+    fn test_compile_str_parentheses() {
+        let res = compile_str("LET a = 1\nLET b = (a + 2) * -(a - 4)\nIF NOT (a > 1 OR b < 0) THEN\nENDIF\n", &CompileOptions::default()).unwrap();
+        assert!(res.contains("b_num = (a_num+2)*-(a_num-4);\n"));
+        assert!(res.contains("if (!(a_num>1||b_num<0)) {\n"));
+    }
+
+    #[test]
+    fn test_compile_str_unary_signs() {
+        let res = compile_str("LET a = 1\nLET b = 5 - -a\nLET c = 5 + +3 - -a * 2\nLET d = -a - 1\n", &CompileOptions::default()).unwrap();
+        assert!(res.contains("b_num = 5- -a_num;\n"));
+        assert!(res.contains("c_num = 5+ +3- -a_num*2;\n"));
+        assert!(res.contains("d_num = -a_num-1;\n"));
+    }
+
+    #[test]
+    fn test_compile_str_includes() {
+        let res = compile_str("PRINT 2\n", &CompileOptions::default()).unwrap();
+        assert!(!res.contains("math.h"));
+
+        let res = compile_str("PRINT ABS(2.5)\n", &CompileOptions::default()).unwrap();
+        assert!(res.starts_with("/* link with -lm */\n#include <stdio.h>\n#include <math.h>\n"));
+
+        let res = compile_str("PRINT RND(2)\n", &CompileOptions::default()).unwrap();
+        assert!(!res.contains("math.h"));
+        assert!(res.contains("static double rnd(double range){\n"));
+    }
+
+    #[test]
+    fn test_compile_str_static_arrays() {
+        let res = compile_str("DIM a(2000000)\nFUNCTION f(x)\nDIM b(3)\nRETURN x\nENDFUNCTION\n", &CompileOptions::default()).unwrap();
+        assert!(res.contains("int main(void){\nstatic long long a_arr[2000001] = {0};\n"));
+        assert!(res.contains("long long fn_f(long long x_num){\nlong long b_arr[4] = {0};\nreturn x_num;\n"));
+    }
+
+    #[test]
+    fn test_run_recursive_arrays() {
+        let source = "FUNCTION f(n)\nDIM a(2)\nLET a(0) = n\nIF n > 0 THEN\nLET t = f(n - 1)\nENDIF\nRETURN a(0)\nENDFUNCTION\nPRINT f(3)\n";
+        assert_eq!(run("recursive_arrays", source, ""), "3\n");
+    }
+
+    #[test]
+    fn test_compile_str_bounds_checks() {
+        let source = "DIM a(3)\nLET a(2) = 1\n";
+
+        let res = compile_str(source, &CompileOptions::default()).unwrap();
+        assert!(res.contains("a_arr[(int)(2)] = 1;\n"));
+        assert!(!res.contains("bounds_check"));
+
+        let options = CompileOptions { bounds_checks: true, ..CompileOptions::default() };
+        let res = compile_str(source, &options).unwrap();
+        assert!(res.starts_with("#include <stdio.h>\n#include <stdlib.h>\n"));
+        assert!(res.contains("a_arr[bounds_check(2, 4, 2, 5)] = 1;\n"));
+    }
+
+    #[test]
+    fn test_compile_str_errors() {
+        let options = CompileOptions { max_errors: 1, ..CompileOptions::default() };
+        let res = compile_str("PRINT a\nPRINT b\n", &options).unwrap_err();
+
+        assert_eq!(res.len(), 1);
+        assert!(matches!(res.errors[0], error::CompileError::Semantic { .. }));
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let kinds = tokenize("LET a = 1\n").unwrap().iter()
+            .map(|token| token.kind)
+            .collect::<Vec<_>>();
+
+        assert_eq!(kinds, vec![
+            lex::TokenType::LET,
+            lex::TokenType::IDENT,
+            lex::TokenType::EQ,
+            lex::TokenType::NUMBER,
+            lex::TokenType::NEWLINE,
+        ]);
+
+        assert_eq!(tokenize("LET a = 1 $ 2 @\n").unwrap_err().len(), 2);
+    }
+}
@@ -1,7 +1,4 @@
-#[path = "../src/lex.rs"] mod lex;
-#[path = "../src/parse.rs"] mod parse;
-#[path = "../src/emitter.rs"] mod emitter;
-#[path = "../src/utils/utils.rs"] mod utils;
+use simple_compiler::{ast, lex, parse, error, utils};
 
 use std::path::PathBuf;
 
@@ -14,13 +11,371 @@ mod tests {
 
         let input = "src/examples/tests/test_5.txt";
 
-        let mut parser = parse::Parser::new(
-            lex::Lexer::new(utils::read_file(&PathBuf::from(input))),
-            emitter::Emitter::new(PathBuf::from("out.c")),
+        let source = utils::read_file(&PathBuf::from(input)).unwrap();
+        let mut parser = parse::Parser::new(lex::Lexer::new(&source));
+
+        let program = parser.program().unwrap();
+
+        assert_eq!(program.statements, vec![
+            ast::Statement::Print { items: vec![ast::Printable::Text(String::from("Hello World!"))], newline: true, format: None },
+        ]);
+    }
+
+    #[test]
+    fn test_parser_nested_blocks() {
+
+        let input = "src/examples/tests/test_7.txt";
+
+        let source = utils::read_file(&PathBuf::from(input)).unwrap();
+        let mut parser = parse::Parser::new(lex::Lexer::new(&source));
+
+        let program = parser.program().unwrap();
+
+        assert_eq!(program.statements.len(), 3);
+        match &program.statements[2] {
+            ast::Statement::If { body, .. } => {
+                assert!(matches!(body[0], ast::Statement::If { .. }));
+            },
+            other => panic!("Expected IF statement, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parser_else_branches() {
+        let source = concat!(
+            "LET a = 1\n",
+            "IF a > 1 THEN\n",
+            "    PRINT \"big\"\n",
+            "ELSEIF a == 1 THEN\n",
+            "    PRINT \"one\"\n",
+            "ELSE\n",
+            "    PRINT \"small\"\n",
+            "ENDIF\n",
         );
 
-        parser.program();
+        let mut parser = parse::Parser::new(lex::Lexer::new(source));
+        let program = parser.program().unwrap();
+
+        match &program.statements[1] {
+            ast::Statement::If { body, else_ifs, else_body, .. } => {
+                assert_eq!(body.len(), 1);
+                assert_eq!(else_ifs.len(), 1);
+                assert_eq!(else_body.as_ref().map(Vec::len), Some(1));
+            },
+            other => panic!("Expected IF statement, got {other:?}"),
+        }
+
+        let errors = parse_errors("LET a = 1\nIF a > 1 THEN\nELSE\nELSEIF a < 1 THEN\nENDIF\nELSE\n");
+        let lines = errors.iter().map(|error| error.span().unwrap().line).collect::<Vec<_>>();
+        assert_eq!(lines, vec![4, 6]);
+    }
+
+    #[test]
+    fn test_parser_for_loop() {
+        let mut parser = parse::Parser::new(lex::Lexer::new("FOR i = 1 TO 9 STEP 2\nPRINT i\nNEXT i\n"));
+        let program = parser.program().unwrap();
+
+        match &program.statements[0] {
+            ast::Statement::For { variable, step, body, .. } => {
+                assert_eq!(variable, "i");
+                assert_eq!(step, &Some(ast::Expression::Number(String::from("2"))));
+                assert_eq!(body.len(), 1);
+            },
+            other => panic!("Expected FOR statement, got {other:?}"),
+        }
+
+        let errors = parse_errors("FOR i = 1 TO 3\nNEXT j\n");
+        assert!(matches!(&errors[..], [error::CompileError::Semantic { message, .. }] if message == "NEXT j does not match FOR i"));
+    }
+
+    #[test]
+    fn test_parser_gosub() {
+        let mut parser = parse::Parser::new(lex::Lexer::new("GOSUB sub\nLABEL sub\nRETURN\n"));
+        let program = parser.program().unwrap();
+
+        assert_eq!(program.statements[0], ast::Statement::Gosub { label: String::from("sub"), line: 1 });
+        assert_eq!(program.statements[2], ast::Statement::Return { line: 3, value: None });
+
+        let errors = parse_errors("GOSUB nowhere\nRETURN\n");
+        assert!(matches!(&errors[..], [error::CompileError::Semantic { message, .. }] if message == "GOSUB to undeclared label nowhere"));
+    }
+
+    #[test]
+    fn test_parser_functions() {
+        let source = "LET x = 1\nPRINT add(x, 2)\nFUNCTION add(a, b)\nLET c = a + b\nRETURN c\nENDFUNCTION\n";
+        let mut parser = parse::Parser::new(lex::Lexer::new(source));
+        let program = parser.program().unwrap();
+
+        assert_eq!(program.statements.len(), 2);
+        assert_eq!(program.functions[0].name, "add");
+        assert_eq!(program.functions[0].params, vec!["a", "b"]);
+        assert!(matches!(program.functions[0].body[1], ast::Statement::Return { value: Some(_), .. }));
+
+        // Function bodies cannot see the variables of the main program.
+        let errors = parse_errors("LET x = 1\nFUNCTION f()\nRETURN x\nENDFUNCTION\n");
+        assert!(matches!(&errors[..], [error::CompileError::Semantic { message, .. }] if message == "Referencing variable x before assignment"));
+
+        let errors = parse_errors("FUNCTION f(a)\nRETURN a\nENDFUNCTION\nPRINT f(1, 2)\nPRINT g()\n");
+        let messages = errors.iter().map(|error| error.to_string()).collect::<Vec<_>>();
+        assert_eq!(messages, vec![
+            "[SEMANTIC] Error at 4:7: Function f expects 1 argument, got 2",
+            "[SEMANTIC] Error at 5:7: Call to undeclared function g",
+        ]);
+    }
+
+    #[test]
+    fn test_parser_arrays() {
+        let mut parser = parse::Parser::new(lex::Lexer::new("DIM m(3, 4)\nLET m(1, 2) = 5\nPRINT m(1, 2)\n"));
+        let program = parser.program().unwrap();
+
+        assert_eq!(program.statements[0], ast::Statement::Dim { name: String::from("m"), bounds: vec![3, 4], numeric_type: None });
+        match &program.statements[1] {
+            ast::Statement::SetElement { element, .. } => {
+                assert_eq!(element.array, "m");
+                assert_eq!(element.indices.len(), 2);
+            },
+            other => panic!("Expected element assignment, got {other:?}"),
+        }
+
+        let errors = parse_errors("DIM a(3)\nPRINT a(4)\nPRINT a(1, 2)\nLET a = 1\n");
+        let messages = errors.iter().map(|error| error.to_string()).collect::<Vec<_>>();
+        assert_eq!(messages, vec![
+            "[SEMANTIC] Error at 2:7: Index 4 is out of range for a",
+            "[SEMANTIC] Error at 3:7: Array a expects 1 index, got 2",
+            "[SEMANTIC] Error at 4:5: a is an array, not a variable",
+        ]);
+
+        let errors = parse_errors("DIM b(18446744073709551615)\nDIM c(2147483647)\nDIM d(2147483646, 1.5)\n");
+        let messages = errors.iter().map(|error| error.to_string()).collect::<Vec<_>>();
+        assert_eq!(messages, vec![
+            "[SEMANTIC] Error at 1:7: Array bound 18446744073709551615 is too large",
+            "[SEMANTIC] Error at 2:7: Array bound 2147483647 is too large",
+            "[SEMANTIC] Error at 3:19: Array bound must be a whole number, got 1.5",
+        ]);
+    }
+
+    #[test]
+    fn test_parser_strings() {
+        let mut parser = parse::Parser::new(lex::Lexer::new("INPUT a$\nLET b$ = \"x\" + a$\nIF b$ != a$ THEN\nPRINT b$\nENDIF\n"));
+        let program = parser.program().unwrap();
+
+        match &program.statements[1] {
+            ast::Statement::Let { name, value } => {
+                assert_eq!(name, "b$");
+                assert!(value.is_text());
+            },
+            other => panic!("Expected LET statement, got {other:?}"),
+        }
+
+        let errors = parse_errors("LET a = \"x\"\nLET b$ = \"x\" * \"y\"\nPRINT \"x\" + 1\n");
+        let messages = errors.iter().map(|error| error.to_string()).collect::<Vec<_>>();
+        assert_eq!(messages, vec![
+            "[SEMANTIC] Error at 1:9: Expected a number, found a string",
+            "[SEMANTIC] Error at 2:14: Operator * is not defined for strings",
+            "[SEMANTIC] Error at 3:11: Operator + cannot mix a string and a number",
+        ]);
+
+        let errors = parse_errors("INPUT a$\nINPUT b$\nLET x = (a$ OR b$)\nIF (a$ AND 1 > 0) THEN\nENDIF\nIF (NOT a$) THEN\nENDIF\n");
+        let messages = errors.iter().map(|error| error.to_string()).collect::<Vec<_>>();
+        assert_eq!(messages, vec![
+            "[SEMANTIC] Error at 3:13: Operator OR is not defined for strings",
+            "[SEMANTIC] Error at 4:8: Operator AND cannot mix a string and a number",
+            "[SEMANTIC] Error at 6:5: Operator NOT is not defined for strings",
+        ]);
+    }
+
+    #[test]
+    fn test_parser_boolean_operators() {
+        let mut parser = parse::Parser::new(lex::Lexer::new("LET a = 1\nWHILE a > 1 OR a < 5 AND NOT a == 3 REPEAT\nENDWHILE\n"));
+        let program = parser.program().unwrap();
+
+        let compare = |op, value: &str| ast::Expression::Binary {
+            op,
+            lhs: Box::new(ast::Expression::Variable(String::from("a"))),
+            rhs: Box::new(ast::Expression::Number(value.to_string())),
+        };
+        let not = ast::Expression::Unary { op: ast::UnaryOp::Not, operand: Box::new(compare(ast::BinaryOp::Eq, "3")) };
+        let and = ast::Expression::Binary { op: ast::BinaryOp::And, lhs: Box::new(compare(ast::BinaryOp::Lt, "5")), rhs: Box::new(not) };
+        let or = ast::Expression::Binary { op: ast::BinaryOp::Or, lhs: Box::new(compare(ast::BinaryOp::Gt, "1")), rhs: Box::new(and) };
+
+        assert_eq!(program.statements[1], ast::Statement::While { condition: or, body: Vec::new() });
+    }
+
+    #[test]
+    fn test_parser_parentheses() {
+        let mut parser = parse::Parser::new(lex::Lexer::new("LET a = 1\nLET b = (a + 2) * a\nIF (a > 1 OR a < 0) AND a != 3 THEN\nENDIF\n"));
+        let program = parser.program().unwrap();
+
+        match &program.statements[1] {
+            ast::Statement::Let { value: ast::Expression::Binary { op, lhs, .. }, .. } => {
+                assert_eq!(*op, ast::BinaryOp::Mul);
+                assert!(matches!(**lhs, ast::Expression::Group(_)));
+            },
+            other => panic!("Expected LET statement, got {other:?}"),
+        }
+        assert!(matches!(&program.statements[2], ast::Statement::If { condition: ast::Expression::Binary { op: ast::BinaryOp::And, .. }, .. }));
+
+        let errors = parse_errors("LET a = 1\nIF (a) THEN\nENDIF\nPRINT (a + 1\n");
+        let messages = errors.iter().map(|error| error.to_string()).collect::<Vec<_>>();
+        assert_eq!(messages, vec![
+            "[PARSER] Error at 2:8: Expected comparison operator at: THEN",
+            "[PARSER] Error at 4:13: Expecting token of type RPAREN, got NEWLINE",
+        ]);
+    }
+
+    #[test]
+    fn test_parser_power_and_modulo() {
+        let number = |value: &str| Box::new(ast::Expression::Number(value.to_string()));
+        let parse_value = |source: &str| {
+            let mut parser = parse::Parser::new(lex::Lexer::new(source));
+            match parser.program().unwrap().statements.remove(0) {
+                ast::Statement::Let { value, .. } => value,
+                other => panic!("Expected LET statement, got {other:?}"),
+            }
+        };
+
+        let pow = |lhs, rhs| ast::Expression::Binary { op: ast::BinaryOp::Pow, lhs, rhs };
+        assert_eq!(parse_value("LET a = 2 ^ 3 ^ 2\n"), pow(number("2"), Box::new(pow(number("3"), number("2")))));
+        assert_eq!(parse_value("LET a = -2 ^ 2\n"), ast::Expression::Unary { op: ast::UnaryOp::Minus, operand: Box::new(pow(number("2"), number("2"))) });
+
+        let product = ast::Expression::Binary { op: ast::BinaryOp::Mul, lhs: number("7"), rhs: number("2") };
+        let modulo = ast::Expression::Binary { op: ast::BinaryOp::Mod, lhs: Box::new(product), rhs: number("4") };
+        assert_eq!(parse_value("LET a = 7 * 2 MOD 4\n"), modulo);
+        assert_eq!(parse_value("LET a = 7 * 2 % 4\n"), modulo);
+    }
+
+    #[test]
+    fn test_parser_intrinsics() {
+        let mut parser = parse::Parser::new(lex::Lexer::new("RANDOMIZE 7\nPRINT SQRT(RND(10))\n"));
+        let program = parser.program().unwrap();
+
+        assert!(matches!(program.statements[0], ast::Statement::Randomize(_)));
+        assert!(matches!(&program.statements[1], ast::Statement::Print { items, .. } if matches!(items[..], [ast::Printable::Expr(ast::Expression::Intrinsic { function: ast::Intrinsic::Sqrt, .. })])));
+
+        let errors = parse_errors("PRINT ABS(1, 2)\nDIM SIN(3)\n");
+        let messages = errors.iter().map(|error| error.to_string()).collect::<Vec<_>>();
+        assert_eq!(messages, vec![
+            "[SEMANTIC] Error at 1:7: Function ABS expects 1 argument, got 2",
+            "[SEMANTIC] Error at 2:5: SIN is a built-in function",
+        ]);
+    }
+
+    #[test]
+    fn test_parser_print_list() {
+        let mut parser = parse::Parser::new(lex::Lexer::new("LET a = 1\nPRINT \"a: \"; a, a;\nPRINT a,\nPRINT\n"));
+        let program = parser.program().unwrap();
+
+        let a = || ast::Printable::Expr(ast::Expression::Variable(String::from("a")));
+        assert_eq!(program.statements[1..], vec![
+            ast::Statement::Print { items: vec![ast::Printable::Text(String::from("a: ")), a(), ast::Printable::Zone, a()], newline: false, format: None },
+            ast::Statement::Print { items: vec![a(), ast::Printable::Zone], newline: false, format: None },
+            ast::Statement::Print { items: vec![], newline: true, format: None },
+        ]);
+
+        let errors = parse_errors("PRINT 1;;\nPRINT 1 2\n");
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn test_parser_print_using() {
+        let mut parser = parse::Parser::new(lex::Lexer::new("PRINT USING \"+00.##\"; 1, 2;\nPRINT USING \"###\"; 3\n"));
+        let program = parser.program().unwrap();
+
+        let format = ast::Format { width: 6, precision: 2, sign: true, zeros: true };
+        assert!(matches!(&program.statements[0], ast::Statement::Print { newline: false, format: Some(f), .. } if *f == format));
+        assert!(matches!(&program.statements[1], ast::Statement::Print { newline: true, format: Some(ast::Format { width: 3, precision: 0, sign: false, zeros: false }), .. }));
+
+        let errors = parse_errors("PRINT USING \"#0.#\"; 1\nPRINT USING \"##.\"; 1\nPRINT USING \"x#\"; 1\nPRINT USING \"##\"; \"a\"\nPRINT USING \"##\";\n");
+        let messages = errors.iter().map(|error| error.to_string()).collect::<Vec<_>>();
+        assert_eq!(messages, vec![
+            "[SEMANTIC] Error at 1:13: Invalid PRINT USING format \"#0.#\"",
+            "[SEMANTIC] Error at 2:13: Invalid PRINT USING format \"##.\"",
+            "[SEMANTIC] Error at 3:13: Invalid PRINT USING format \"x#\"",
+            "[SEMANTIC] Error at 4:19: PRINT USING can only format numbers",
+            "[PARSER] Error at 5:18: PRINT USING needs a value to format",
+        ]);
+    }
+
+    #[test]
+    fn test_parser_input() {
+        let mut parser = parse::Parser::new(lex::Lexer::new("INPUT \"Name and age: \"; n$, a\nINPUT b\nPRINT a + b\n"));
+        let program = parser.program().unwrap();
+
+        assert_eq!(program.statements[..2], vec![
+            ast::Statement::Input { prompt: Some(String::from("Name and age: ")), names: vec![String::from("n$"), String::from("a")] },
+            ast::Statement::Input { prompt: None, names: vec![String::from("b")] },
+        ]);
+
+        let errors = parse_errors("INPUT \"x\" a\nINPUT a,\n");
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn test_parser_annotations() {
+        let mut parser = parse::Parser::new(lex::Lexer::new("FUNCTION f(a AS INTEGER, b) AS FLOAT\nRETURN a\nENDFUNCTION\nDIM x AS INTEGER\nDIM v(2) AS FLOAT\n"));
+        let program = parser.program().unwrap();
+
+        assert_eq!(program.functions[0].param_types, vec![Some(ast::NumericType::Integer), None]);
+        assert_eq!(program.functions[0].return_type, Some(ast::NumericType::Float));
+        assert_eq!(program.statements[0], ast::Statement::Dim { name: String::from("x"), bounds: vec![], numeric_type: Some(ast::NumericType::Integer) });
+        assert_eq!(program.statements[1], ast::Statement::Dim { name: String::from("v"), bounds: vec![2], numeric_type: Some(ast::NumericType::Float) });
+
+        let errors = parse_errors("DIM x\nLET y = 1\nDIM y AS FLOAT\nDIM z AS TEXT\n");
+        let messages = errors.iter().map(|error| error.to_string()).collect::<Vec<_>>();
+        assert_eq!(messages.len(), 3);
+    }
+
+    fn parse_errors(source: &str) -> Vec<error::CompileError> {
+        let mut parser = parse::Parser::new(lex::Lexer::new(source));
+        parser.program().unwrap_err()
+    }
+
+    #[test]
+    fn test_parser_errors() {
+        let errors = parse_errors("PRINT a\n");
+        assert!(matches!(errors[..], [error::CompileError::Semantic { .. }]));
+
+        let errors = parse_errors("LET a = 1\nIF a > 0 THEN\nPRINT a");
+        match &errors[0] {
+            error::CompileError::Syntax { message, span } => {
+                assert_eq!(message, "Expecting token of type NEWLINE, got EOF");
+                assert_eq!((span.line, span.column), (3, 8));
+            },
+            other => panic!("Expected syntax error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parser_recovery() {
+        let source = concat!(
+            "LET a = 1\n",
+            "LET = 2\n",
+            "IF a THEN\n",
+            "    PRINT b\n",
+            "ENDIF\n",
+            "WHILE a < 3 REPEAT\n",
+            "    LET a = a + $\n",
+            "ENDWHILE\n",
+            "GOTO nowhere\n",
+        );
+
+        let lines = parse_errors(source).iter()
+            .map(|error| error.span().unwrap().line)
+            .collect::<Vec<_>>();
+
+        assert_eq!(lines, vec![2, 3, 4, 7, 7, 9]);
+    }
+
+    #[test]
+    fn test_parser_error_limit() {
+        let mut parser = parse::Parser::new(lex::Lexer::new("PRINT a\nPRINT b\nPRINT c\n"));
+        parser.max_errors = 2;
+
+        assert_eq!(parser.program().unwrap_err().len(), 2);
+
+        let mut parser = parse::Parser::new(lex::Lexer::new("PRINT a\nLET =\nPRINT c\n"));
+        parser.max_errors = 0;
 
-        assert!(true);
+        assert_eq!(parser.program().unwrap_err().len(), 3);
     }
 }
@@ -0,0 +1,40 @@
+use simple_compiler::{compile_str, utils, CompileOptions};
+
+use std::path::PathBuf;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_utf8() {
+        let source = utils::read_file(&PathBuf::from("src/examples/tests/test_9.txt")).unwrap();
+        let res = compile_str(&source, &CompileOptions::default()).unwrap();
+
+        assert!(res.contains("fputs(\"Olá, coração\\n\", stdout);"));
+    }
+
+    #[test]
+    fn test_read_utf16() {
+        let source = utils::read_file(&PathBuf::from("src/examples/tests/test_10.txt")).unwrap();
+        assert_eq!(source, "PRINT \"Olá\"\n");
+    }
+
+    #[test]
+    fn test_decode_bom() {
+        let res = utils::decode_source(b"\xEF\xBB\xBFPRINT \"Ol\xC3\xA1\"\n").unwrap();
+        assert_eq!(res, "PRINT \"Olá\"\n");
+
+        let res = utils::decode_source(b"\xFE\xFF\x00A\x00\xE1").unwrap();
+        assert_eq!(res, "Aá");
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        let res = utils::decode_source(b"PRINT \"ok\"\nPRINT \"Ol\xE1\"\n").unwrap_err();
+        assert_eq!(res, "Invalid UTF-8 byte 0xE1 at line 2, column 10");
+
+        assert!(utils::decode_source(b"\xFF\xFEA").is_err());
+        assert!(utils::decode_source(b"\xFF\xFE\x00\xD8").is_err());
+    }
+}